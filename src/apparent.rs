@@ -0,0 +1,121 @@
+//! Apparent place, where an object is seen from the center of the Earth
+//!
+//! The mean place, which is what positions are otherwise given as, is the geometric position referred to the
+//! mean equator and equinox of date. The apparent place also has the light-time, annual aberration (up to 20″),
+//! and nutation (up to 17″ in longitude and 9″ in obliquity) applied, following Meeus (Astronomical
+//! Algorithms, ch. 22 and 23).
+
+use crate::moons;
+use crate::query::{self, Property};
+use crate::value::{CelObj, Epoch, Place, RefFrame, Value};
+use pracstro::{coord, time};
+
+/// Light-time for one AU, in days
+pub const LIGHT_TIME: f64 = 0.0057755183;
+
+/// Constant of aberration, in degrees
+const ABERRATION: f64 = 20.49552 / 3600.0;
+
+/// Nutation in longitude and obliquity, in degrees, from the largest terms of the IAU 1980 theory
+///
+/// These are good to about half an arcsecond.
+pub fn nutation(d: time::Date) -> (f64, f64) {
+    let t = d.centuries();
+    // Longitude of the Moon's node, and the mean longitudes of the Sun and Moon
+    let node = (125.04452 - 1934.136261 * t).to_radians();
+    let sun = (280.4665 + 36000.7698 * t).to_radians();
+    let moon = (218.3165 + 481267.8813 * t).to_radians();
+    let longitude = -17.20 * node.sin() - 1.32 * (2.0 * sun).sin() - 0.23 * (2.0 * moon).sin()
+        + 0.21 * (2.0 * node).sin();
+    let obliquity = 9.20 * node.cos() + 0.57 * (2.0 * sun).cos() + 0.10 * (2.0 * moon).cos()
+        - 0.09 * (2.0 * node).cos();
+    (longitude / 3600.0, obliquity / 3600.0)
+}
+
+/// Moves a position from the mean to the true equator and equinox (Meeus 23.1)
+fn nutate(c: coord::Coord, d: time::Date) -> coord::Coord {
+    let (dpsi, deps) = nutation(d);
+    let e = coord::mean_obliquity_ecl(d).radians();
+    let (ra, de) = c.equatorial();
+    let (ra, de) = (ra.radians(), de.to_latitude().radians());
+    let dra = (e.cos() + e.sin() * ra.sin() * de.tan()) * dpsi - ra.cos() * de.tan() * deps;
+    let dde = e.sin() * ra.cos() * dpsi + ra.sin() * deps;
+    coord::Coord::from_equatorial(
+        time::Angle::from_degrees(ra.to_degrees() + dra),
+        time::Angle::from_degrees(de.to_degrees() + dde),
+    )
+}
+
+/// Annual aberration from the Earth's orbital motion, for objects with no light-time to correct (Meeus 23.3)
+fn aberrate(c: coord::Coord, d: time::Date) -> Result<coord::Coord, &'static str> {
+    let t = d.centuries();
+    let sun = crate::events::longitude(&CelObj::Sun, d)?.to_radians();
+    // Eccentricity and longitude of perihelion of the Earth's orbit
+    let ecc = 0.016708634 - 0.000042037 * t;
+    let peri = (102.93735 + 1.71946 * t).to_radians();
+    let e = coord::mean_obliquity_ecl(d).radians();
+    let (ra, de) = c.equatorial();
+    let (ra, de) = (ra.radians(), de.to_latitude().radians());
+
+    let dra = (-(ra.cos() * sun.cos() * e.cos() + ra.sin() * sun.sin())
+        + ecc * (ra.cos() * peri.cos() * e.cos() + ra.sin() * peri.sin()))
+        * ABERRATION
+        / de.cos();
+    let dde = (-(sun.cos() * e.cos() * (e.tan() * de.cos() - ra.sin() * de.sin())
+        + ra.cos() * de.sin() * sun.sin())
+        + ecc
+            * (peri.cos() * e.cos() * (e.tan() * de.cos() - ra.sin() * de.sin())
+                + ra.cos() * de.sin() * peri.sin()))
+        * ABERRATION;
+    Ok(coord::Coord::from_equatorial(
+        time::Angle::from_degrees(ra.to_degrees() + dra),
+        time::Angle::from_degrees(de.to_degrees() + dde),
+    ))
+}
+
+/// Apparent equatorial coordinates of an object, referred to the true equator and equinox of date
+///
+/// The Sun, planets, and minor bodies are seen where they were when the light left them, from where the Earth is now.
+/// Taking their geocentric position one light-time ago accounts for both that and the aberration. The
+/// lunar theory already includes its aberration, and satellites are close enough that their light-time is a
+/// few milliseconds. The moons of planets are offset from where their planet is seen. Raw J2000 coordinates are treated as stars are, and those of date are left as they are.
+pub fn place(obj: &CelObj, rf: &RefFrame) -> Result<coord::Coord, &'static str> {
+    let mean = |date: time::Date| -> Result<coord::Coord, &'static str> {
+        let frame = RefFrame {
+            date,
+            place: Place::Mean,
+            ..*rf
+        };
+        let Value::Crd(c, _) = query::property_of(obj, Property::Equatorial, &frame)? else {
+            unreachable!();
+        };
+        Ok(c)
+    };
+    Ok(match obj {
+        CelObj::Planet(p) => nutate(
+            mean(time::Date::from_julian(
+                rf.date.julian() - LIGHT_TIME * p.distance(rf.date),
+            ))?,
+            rf.date,
+        ),
+        CelObj::Minor(o) => nutate(
+            mean(time::Date::from_julian(
+                rf.date.julian() - LIGHT_TIME * o.distance(rf.date),
+            ))?,
+            rf.date,
+        ),
+        CelObj::Sun => nutate(
+            mean(time::Date::from_julian(
+                rf.date.julian() - LIGHT_TIME * pracstro::sol::SUN.distance(rf.date),
+            ))?,
+            rf.date,
+        ),
+        CelObj::Moon => nutate(mean(rf.date)?, rf.date),
+        CelObj::Star(_) | CelObj::Crd(_, Epoch::J2000) => {
+            nutate(aberrate(mean(rf.date)?, rf.date)?, rf.date)
+        }
+        CelObj::Crd(c, Epoch::OfDate) => *c,
+        CelObj::Satellite(_) => mean(rf.date)?,
+        CelObj::PlanetMoon(m) => moons::shift(place(&CelObj::Planet(m.planet()), rf)?, *m, rf.date),
+    })
+}
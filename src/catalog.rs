@@ -1,4 +1,5 @@
 use crate::value::*;
+use crate::{mpc, orbit, sgp4};
 use pracstro::{coord, time};
 
 #[derive(Clone, Debug, PartialEq)]
@@ -8,26 +9,96 @@ pub struct Star {
     pub pi: time::Angle,
     pub pm_ra: time::Angle,
     pub pm_dec: time::Angle,
+    /// Radial velocity in km/s, positive receding, if the catalog has it
+    pub rv: Option<f64>,
 }
 
+impl Star {
+    /// Distance in parsecs at J2000, if the parallax is usable
+    fn parsecs(&self) -> Option<f64> {
+        let pi = self.pi.to_latitude().degrees() * 3600.0;
+        (pi > 0.0).then(|| 1.0 / pi)
+    }
+
+    /// Position in space at a date, in parsecs (or on the unit sphere if the distance isn't known),
+    /// with respect to the mean equator and equinox of J2000
+    ///
+    /// The space motion from the proper motion and radial velocity is applied linearly from J2000,
+    /// which is good for many thousands of years, even for Barnard's Star.
+    fn position(&self, d: time::Date) -> [f64; 3] {
+        // km/s to parsecs per Julian year
+        const KMS_TO_PCYR: f64 = 365.25 * 86400.0 / 3.085_677_581_491_367e13;
+
+        let (ra, de) = self.loc_j2k.equatorial();
+        let t = (d.julian() - 2451545.0) / 365.25;
+        let r = self.parsecs().unwrap_or(1.0);
+        // The catalog's RA proper motion already includes the cos δ factor
+        let (mu_a, mu_d) = (
+            self.pm_ra.to_latitude().radians(),
+            self.pm_dec.to_latitude().radians(),
+        );
+        let vr = match (self.rv, self.parsecs()) {
+            (Some(v), Some(_)) => v * KMS_TO_PCYR,
+            _ => 0.0,
+        };
+
+        let (sa, ca, sd, cd) = (ra.sin(), ra.cos(), de.sin(), de.cos());
+        let u = [cd * ca, cd * sa, sd];
+        let east = [-sa, ca, 0.0];
+        let north = [-sd * ca, -sd * sa, cd];
+        [0, 1, 2].map(|i| r * u[i] + t * (r * (mu_a * east[i] + mu_d * north[i]) + vr * u[i]))
+    }
+
+    /// Astrometric position at a date, with respect to the mean equator and equinox of J2000
+    pub fn location(&self, d: time::Date) -> coord::Coord {
+        let [x, y, z] = self.position(d);
+        coord::Coord::from_equatorial(time::Angle::atan2(y, x), time::Angle::atan2(z, x.hypot(y)))
+    }
+
+    /// Distance in AU at a date, if the parallax is usable
+    pub fn distance(&self, d: time::Date) -> Option<f64> {
+        self.parsecs()?;
+        let [x, y, z] = self.position(d);
+        Some((x * x + y * y + z * z).sqrt() * 206_264.806)
+    }
+}
+
+/// Objects by the names they're found by
+pub type Catalog = std::collections::HashMap<String, CelObj>;
+
 /// Creates the catalog as a hash table
 ///
 /// This operation takes about 500 µs on my machine
-pub fn read() -> std::collections::HashMap<&'static str, CelObj> {
+pub fn read() -> Catalog {
+    use crate::moons::Moon;
     use pracstro::sol;
 
-    let mut cat = std::collections::HashMap::from([
+    let mut cat: Catalog = [
         ("sun", CelObj::Sun),
         ("mercury", CelObj::Planet(sol::MERCURY)),
         ("venus", CelObj::Planet(sol::VENUS)),
         ("moon", CelObj::Moon),
+        ("earth", CelObj::Planet(sol::EARTH)),
         ("mars", CelObj::Planet(sol::MARS)),
         ("jupiter", CelObj::Planet(sol::JUPITER)),
         ("saturn", CelObj::Planet(sol::SATURN)),
         ("uranus", CelObj::Planet(sol::URANUS)),
         ("neptune", CelObj::Planet(sol::NEPTUNE)),
         ("pluto", CelObj::Planet(sol::PLUTO)),
-    ]);
+        ("io", CelObj::PlanetMoon(Moon::Io)),
+        ("europa", CelObj::PlanetMoon(Moon::Europa)),
+        ("ganymede", CelObj::PlanetMoon(Moon::Ganymede)),
+        ("callisto", CelObj::PlanetMoon(Moon::Callisto)),
+        ("mimas", CelObj::PlanetMoon(Moon::Mimas)),
+        ("enceladus", CelObj::PlanetMoon(Moon::Enceladus)),
+        ("tethys", CelObj::PlanetMoon(Moon::Tethys)),
+        ("dione", CelObj::PlanetMoon(Moon::Dione)),
+        ("rhea", CelObj::PlanetMoon(Moon::Rhea)),
+        ("titan", CelObj::PlanetMoon(Moon::Titan)),
+    ]
+    .into_iter()
+    .map(|(n, o)| (n.to_string(), o))
+    .collect();
 
     include_str!("dat/stars.csv")
         .lines()
@@ -45,12 +116,223 @@ pub fn read() -> std::collections::HashMap<&'static str, CelObj> {
                     pi: time::Angle::from_degrees(p[4].parse::<f64>().unwrap() / 3_600_000.0),
                     pm_ra: time::Angle::from_degrees(p[5].parse::<f64>().unwrap() / 3_600_000.0),
                     pm_dec: time::Angle::from_degrees(p[6].parse::<f64>().unwrap() / 3_600_000.0),
+                    // The radial velocity in km/s, which is only given for the nearest and fastest stars
+                    rv: p
+                        .get(7)
+                        .filter(|v| !v.is_empty())
+                        .map(|v| v.parse().unwrap()),
                 }),
             )
         })
         .for_each(|(n, s)| {
-            cat.insert(n, s);
+            cat.insert(n.to_string(), s);
+        });
+
+    // Asteroids are given with the semi-major axis and the mean anomaly at the epoch, and comets with the
+    // perihelion distance and time
+    include_str!("dat/minor.csv")
+        .lines()
+        .skip(1)
+        .map(|body| {
+            let p: Vec<&str> = body.split(',').collect();
+            let num = |i: usize| -> f64 { p[i].parse().unwrap() };
+            let (h, slope) = (num(9), num(10));
+            let (q, tp, magnitude) = match p[1] {
+                "asteroid" => (
+                    num(3) * (1.0 - num(4)),
+                    orbit::perihelion(time::Date::from_julian(num(2)), num(3), num(8)),
+                    orbit::Magnitude::Asteroid { h, g: slope },
+                ),
+                _ => (
+                    num(3),
+                    time::Date::from_julian(num(8)),
+                    orbit::Magnitude::Comet { h, k: slope },
+                ),
+            };
+            (
+                p[0],
+                CelObj::Minor(orbit::Orbit {
+                    q,
+                    e: num(4),
+                    i: num(5),
+                    node: num(6),
+                    peri: num(7),
+                    tp,
+                    magnitude,
+                }),
+            )
+        })
+        .for_each(|(n, o)| {
+            cat.insert(n.to_string(), o);
         });
 
     cat
 }
+
+/// Objects and the names they're found by
+pub type Bodies = Vec<(String, CelObj)>;
+
+/// Reads minor planets and comets from a file of orbital elements in the MPC's formats
+///
+/// The header of an MPCORB file, down to its line of dashes, is skipped. Malformed lines are left out, and
+/// returned as errors with their line numbers, since a file of a million objects shouldn't be unusable for
+/// one bad line. So are names that are already in `builtin`, which are kept as they are.
+pub fn read_elements(path: &str, builtin: &Catalog) -> Result<(Bodies, Vec<String>), String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let mut lines: Vec<(usize, &str)> = text.lines().enumerate().map(|(n, l)| (n + 1, l)).collect();
+    if let Some(header) = lines.iter().position(|(_, l)| l.starts_with("-----")) {
+        lines.drain(..=header);
+    }
+
+    let mut bodies = Vec::new();
+    let mut errors = Vec::new();
+    for (n, line) in lines.into_iter().filter(|(_, l)| !l.trim().is_empty()) {
+        match mpc::parse_line(line) {
+            Ok((names, orbit)) => {
+                for name in names {
+                    if builtin.contains_key(&name) {
+                        errors.push(format!(
+                            "{}:{}: {} is already in the catalog",
+                            path, n, name
+                        ));
+                    } else {
+                        bodies.push((name, CelObj::Minor(orbit.clone())));
+                    }
+                }
+            }
+            Err(e) => errors.push(format!("{}:{}: {}", path, n, e)),
+        }
+    }
+    Ok((bodies, errors))
+}
+
+/// The names a satellite can be looked up by, its catalog number and its name in lowercase
+///
+/// Names with a part in parentheses can also be found without it, so `ISS (ZARYA)` is also `iss`.
+fn satellite_names(name: Option<&str>, number: u32) -> Vec<String> {
+    let mut names = vec![number.to_string()];
+    if let Some(name) = name {
+        // Space-Track's three-line format puts a 0 before the name
+        let name = name
+            .strip_prefix("0 ")
+            .unwrap_or(name)
+            .trim()
+            .to_lowercase();
+        if let Some((short, _)) = name.split_once(" (") {
+            names.push(short.trim().to_string());
+        }
+        names.push(name);
+    }
+    names
+}
+
+/// Reads satellites from a file of two-line element sets, each optionally after a line with its name
+///
+/// As with [`read_elements()`], malformed element sets and names already in `builtin` are left out, and
+/// returned as errors with their line numbers.
+pub fn read_tle(path: &str, builtin: &Catalog) -> Result<(Bodies, Vec<String>), String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(n, l)| (n + 1, l))
+        .filter(|(_, l)| !l.trim().is_empty())
+        .peekable();
+
+    let mut bodies = Vec::new();
+    let mut errors = Vec::new();
+    let mut name = None;
+    while let Some((n, line)) = lines.next() {
+        if line.starts_with("2 ") {
+            errors.push(format!("{}:{}: Line 2 without a line 1 before it", path, n));
+            name = None;
+            continue;
+        } else if !line.starts_with("1 ") {
+            name = Some(line);
+            continue;
+        }
+        let Some((_, line2)) = lines.next_if(|(_, l)| l.starts_with("2 ")) else {
+            errors.push(format!("{}:{}: Line 1 without a line 2 after it", path, n));
+            name = None;
+            continue;
+        };
+        match sgp4::Satellite::from_tle(line, line2) {
+            Ok(sat) => {
+                for name in satellite_names(name, sat.number) {
+                    if builtin.contains_key(&name) {
+                        errors.push(format!(
+                            "{}:{}: {} is already in the catalog",
+                            path, n, name
+                        ));
+                    } else {
+                        bodies.push((name, CelObj::Satellite(Box::new(sat.clone()))));
+                    }
+                }
+            }
+            Err(e) => errors.push(format!("{}:{}: {}", path, n, e)),
+        }
+        name = None;
+    }
+    Ok((bodies, errors))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncated_tle() {
+        let path =
+            std::env::temp_dir().join(format!("cgi-ephem-truncated-{}.tle", std::process::id()));
+        std::fs::write(
+            &path,
+            "VANGUARD 1\n1 00005U 58002B   00179.78495062  .00000023  00000-0  28098-4 0  4753\n",
+        )
+        .unwrap();
+        let (bodies, errors) = read_tle(path.to_str().unwrap(), &read()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(bodies.is_empty());
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].ends_with(":2: Line 1 without a line 2 after it"));
+    }
+
+    #[test]
+    fn builtin_names_kept() {
+        let path =
+            std::env::temp_dir().join(format!("cgi-ephem-builtin-{}.tle", std::process::id()));
+        std::fs::write(
+            &path,
+            "MOON\n\
+             1 00005U 58002B   00179.78495062  .00000023  00000-0  28098-4 0  4753\n\
+             2 00005  34.2682 348.7242 1859667 331.7664  19.3264 10.82419157413667\n",
+        )
+        .unwrap();
+        let (bodies, errors) = read_tle(path.to_str().unwrap(), &read()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            bodies.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>(),
+            ["5"]
+        );
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].ends_with(":2: moon is already in the catalog"));
+    }
+
+    #[test]
+    fn radial_velocity() {
+        let cat = read();
+        let Some(CelObj::Star(barnard)) = cat.get("barnard's star") else {
+            panic!("No Barnard's Star");
+        };
+        assert_eq!(barnard.rv, Some(-110.5));
+        // It comes 0.0113 pc closer in a century, along with the change from its proper motion
+        let (now, later) = (
+            barnard
+                .distance(time::Date::from_julian(2451545.0))
+                .unwrap(),
+            barnard
+                .distance(time::Date::from_julian(2451545.0 + 36525.0))
+                .unwrap(),
+        );
+        assert!((206264.8 * 0.0113 - (now - later)).abs() < 20.0);
+    }
+}
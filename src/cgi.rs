@@ -0,0 +1,165 @@
+//! Running as a CGI script (RFC 3875)
+//!
+//! The format and object can be given in the path (`/cgi-ephem/html/moon`), or in the query string
+//! (`?object=moon&format=html&date=2026-10-17&latlong=40n,74w`). The query string takes precedence.
+//!
+//! Property queries are made with `mode=query&props=equ,phase`, and ephemerides with
+//! `ephem=2026-01-01,1d,2026-02-01&props=equ,phase`. The phases of the Moon are listed with
+//! `mode=phases&range=2026-01-01,2026-12-31`, and the equinoxes and solstices with
+//! `mode=seasons&year=2026`. Eclipses are listed with `mode=eclipses&range=2026-01-01,2027-01-01`, and when
+//! they can be seen from the location, if one is given. Conjunctions are listed with
+//! `mode=conjunctions&object=venus&other=jupiter`, or without `other` for the oppositions and elongations of
+//! a planet. Occultations of stars and planets by the Moon are listed with `mode=occultations`, which needs a
+//! location, as are the passes of a satellite over it with `mode=passes&object=iss`. The transits,
+//! occultations, and eclipses of Jupiter's moons are listed with `mode=jovian`. Positions are apparent
+//! places with `place=apparent`, and seen from another body with `from=mars`.
+
+use crate::request::{self, Error, Mode, Request};
+use crate::value::Place;
+use crate::{horizon, json, parse, text};
+use pracstro::time;
+use std::env;
+
+/// Builds a request out of the PATH_INFO and QUERY_STRING of a CGI call
+pub fn request(path_info: &str, query_string: &str) -> Result<Request, Error> {
+    let mut req = Request {
+        mode: Mode::Report,
+        format: text::Format::Html,
+        object: String::new(),
+        other: String::new(),
+        properties: String::new(),
+        raw: false,
+        ephem: None,
+        range: None,
+        date: time::Date::now(),
+        latlong: None,
+        elevation: 0.0,
+        pressure: horizon::STANDARD_PRESSURE,
+        temperature: horizon::STANDARD_TEMPERATURE,
+        place: Place::Mean,
+        observer: String::new(),
+        elements: Vec::new(),
+        satellites: Vec::new(),
+    };
+
+    let segments: Vec<&str> = path_info.split('/').filter(|s| !s.is_empty()).collect();
+    match segments.as_slice() {
+        [] => (),
+        [obj] => req.object = parse::urldecode(obj)?,
+        [fmt, obj] => {
+            req.format = parse::format(&parse::urldecode(fmt)?)?;
+            req.object = parse::urldecode(obj)?;
+        }
+        _ => return Err(Error::NotFound(format!("No such path {}", path_info))),
+    }
+
+    let (mut lat, mut long) = (None, None);
+    for pair in query_string.split('&').filter(|s| !s.is_empty()) {
+        let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+        let (k, v) = (parse::urldecode(k)?, parse::urldecode(v)?);
+        match k.as_str() {
+            "object" | "obj" => req.object = v,
+            "other" | "with" => req.other = v,
+            "from" | "observer" => req.observer = v,
+            "format" => req.format = parse::format(&v)?,
+            "mode" => {
+                req.mode = match v.as_str() {
+                    "report" => Mode::Report,
+                    "query" => Mode::Query,
+                    "ephem" => Mode::Ephem,
+                    "phases" => Mode::Phases,
+                    "seasons" => Mode::Seasons,
+                    "eclipses" => Mode::Eclipses,
+                    "conjunctions" => Mode::Conjunctions,
+                    "occultations" => Mode::Occultations,
+                    "passes" => Mode::Passes,
+                    "jovian" => Mode::Jovian,
+                    _ => return Err(Error::BadRequest(format!("Unknown mode {}", v))),
+                }
+            }
+            "props" | "properties" => req.properties = v,
+            "raw" => req.raw = v != "0" && v != "false",
+            "place" => req.place = parse::place(&v)?,
+            "ephem" => {
+                req.mode = Mode::Ephem;
+                req.ephem = Some(parse::ephemq(&v)?);
+            }
+            "range" => req.range = Some(parse::range(&v)?),
+            "year" => req.range = Some(parse::year(&v)?),
+            "date" => req.date = parse::date(&v)?,
+            "latlong" | "location" => req.latlong = parse::latlong(&v)?,
+            "elevation" | "elev" => req.elevation = parse::elevation(&v)?,
+            "pressure" => req.pressure = parse::pressure(&v)?,
+            "temperature" | "temp" => req.temperature = parse::temperature(&v)?,
+            "lat" => lat = Some(v),
+            "long" | "lon" => long = Some(v),
+            _ => return Err(Error::BadRequest(format!("Unknown parameter {}", k))),
+        }
+    }
+    match (lat, long) {
+        (Some(la), Some(lo)) => req.latlong = parse::latlong(&format!("{},{}", la, lo))?,
+        (None, None) => (),
+        _ => return Err("Both lat and long must be given".into()),
+    }
+
+    if req.object.is_empty()
+        && !matches!(
+            req.mode,
+            Mode::Phases | Mode::Seasons | Mode::Eclipses | Mode::Occultations | Mode::Jovian
+        )
+    {
+        return Err("No object specified".into());
+    }
+    match req.mode {
+        Mode::Query if req.properties.is_empty() => {
+            return Err("No properties specified".into());
+        }
+        Mode::Ephem if req.ephem.is_none() => {
+            return Err("No ephemeris range specified".into());
+        }
+        Mode::Ephem if req.properties.is_empty() => {
+            req.properties = request::DEFAULT_EPHEM_PROPERTIES.into();
+        }
+        _ => (),
+    }
+    Ok(req)
+}
+
+/// Reads the request from the environment and prints the response, headers included
+pub fn main() {
+    let path_info = env::var("PATH_INFO").unwrap_or_default();
+    let query_string = env::var("QUERY_STRING").unwrap_or_default();
+
+    // Element files are set by the server, since requests shouldn't be able to read files
+    let paths = |var: &str| -> Vec<String> {
+        env::var_os(var)
+            .map(|p| {
+                env::split_paths(&p)
+                    .map(|p| p.to_string_lossy().into_owned())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let req = request(&path_info, &query_string).map(|r| Request {
+        elements: paths("EPHEM_ELEMENTS"),
+        satellites: paths("EPHEM_SATELLITES"),
+        ..r
+    });
+    // Errors in the request itself are reported in HTML, since the format is unknown
+    let format = req.as_ref().map_or(text::Format::Html, |r| r.format);
+    let driver = format.driver();
+
+    match req.and_then(|r| request::run(&r)) {
+        Ok(out) => print!("{}{}", driver.cgi_header, out),
+        Err(e) if format == text::Format::Json => println!(
+            "{}{}",
+            driver.cgi_status_header(e.status()),
+            json::error(&e.to_string())
+        ),
+        Err(e) => print!(
+            "{}{}",
+            driver.cgi_status_header(e.status()),
+            driver.error_page(&e.to_string())
+        ),
+    }
+}
@@ -0,0 +1,268 @@
+//! Command line argument parsing
+
+use crate::request::{self, Mode, Request};
+use crate::value::Place;
+use crate::{horizon, parse, text};
+use pracstro::time;
+use std::process;
+
+pub const USAGE: &str = "Usage: cgi-ephem [OPTIONS] OBJECT
+       cgi-ephem [OPTIONS] FORMAT OBJECT
+       cgi-ephem [OPTIONS] query OBJECT PROPERTIES
+       cgi-ephem [OPTIONS] phases [START,END]
+       cgi-ephem [OPTIONS] seasons [YEAR]
+       cgi-ephem [OPTIONS] eclipses [START,END]
+       cgi-ephem [OPTIONS] conjunctions OBJECT [OBJECT] [START,END]
+       cgi-ephem -l LAT,LONG [OPTIONS] occultations [START,END]
+       cgi-ephem -l LAT,LONG -s FILE [OPTIONS] passes SATELLITE [START,END]
+       cgi-ephem [OPTIONS] jovian [START,END]
+
+Objects are names from the catalog, or raw coordinates:
+  radec:RA,DEC  ecl:LONG,LAT  gal:L,B
+with ,j2000 (default) or ,date after them for the equinox, in sexagesimal
+(12h30m45s,+12d23m or 12:30:45,-12:23) or decimal. RA is in hours unless
+given in degrees (187.5d).
+
+Properties are a comma separated list of:
+  equ, ecl, gal, sgal, hor, dist, mag, phase, phasename, phaseemoji,
+  phaseangle, illumfrac, angdia, rise, set, transit, angbet:OBJECT
+And for the moons of Jupiter and Saturn, where they are from their planet:
+  offset
+And with a location, as seen from it:
+  topoequ, topodist, topoangdia, appalt, airmass, extmag
+And for the Sun:
+  civildawn, civildusk, nauticaldawn, nauticaldusk, astrodawn, astrodusk,
+  daylength
+And for satellites:
+  sunlit
+
+phases lists the new, first quarter, full, and last quarter moons between
+START and END, or in the month after the date. seasons lists the equinoxes
+and solstices in a year, or the year of the date. eclipses lists the solar
+and lunar eclipses between START and END, or in the year after the date,
+and when they can be seen from the location. conjunctions lists the closest
+approaches of two objects, or the oppositions, conjunctions with the Sun,
+and greatest elongations of a planet, in a range or the year after the date.
+occultations lists when the Moon passes in front of the planets, minor
+planets, and bright stars, seen from the location, in a range or the year
+after the date. passes lists when a satellite rises, culminates, and sets,
+seen from the location, in a range or the week after the date. jovian lists
+the transits, shadow transits, occultations, and eclipses of Jupiter's moons
+in a range or the three days after the date.
+
+Options:
+  -d, --date DATE        Date of the report (default: now)
+                         e.g. 2026-10-17, 2026-10-17T21:30, 2461330.5jd, @1792195200, +3d, -1w
+  -l, --location LAT,LONG
+                         Location of the observer, e.g. 40.7n,74w, or none
+  -E, --elevation METERS Height of the observer above sea level (default: 0)
+  -p, --pressure MBAR    Air pressure, for refraction (default: 1010)
+  -t, --temperature CELSIUS
+                         Air temperature, for refraction (default: 10)
+  -f, --format FORMAT    Output format: text (default), ansi, html, json,
+                         or csv/tsv (for everything but reports)
+  -e, --ephem START,STEP,END
+                         Print a table of the properties (default: equ,dist,mag) over
+                         a range of dates, steps are like 30s, 10min, 1h, 1d, 1w, 1mon, 1y
+  -a, --apparent         Give apparent positions, with light-time, aberration, and
+                         nutation, rather than mean positions
+  -o, --from OBSERVER    See objects from the Sun, the Moon, a planet or moon, or
+                         a point X,Y,Z in AU from the Sun (J2000 ecliptic), rather
+                         than the Earth, in reports, queries, and ephemerides
+  -m, --elements FILE    Add the minor planets or comets in a file of MPC
+                         elements (MPCORB or CometEls format), can be repeated
+  -s, --satellites FILE  Add the satellites in a file of two-line element sets,
+                         can be repeated
+  -r, --raw              Print query values in their machine-readable form
+  -h, --help             Print this help and exit
+  -V, --version          Print the version and exit
+";
+
+/// What the command line asked for
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    Run(Box<Request>),
+    Help,
+    Version,
+}
+
+/// Parses the arguments (without the program name)
+pub fn parse(args: &[String]) -> Result<Action, String> {
+    let mut req = Request {
+        mode: Mode::Report,
+        format: text::Format::Text,
+        object: String::new(),
+        other: String::new(),
+        properties: String::new(),
+        raw: false,
+        ephem: None,
+        range: None,
+        date: time::Date::now(),
+        latlong: None,
+        elevation: 0.0,
+        pressure: horizon::STANDARD_PRESSURE,
+        temperature: horizon::STANDARD_TEMPERATURE,
+        place: Place::Mean,
+        observer: String::new(),
+        elements: Vec::new(),
+        satellites: Vec::new(),
+    };
+    let mut positional: Vec<&str> = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        // Both "--opt value" and "--opt=value" are accepted
+        let (opt, inline) = match arg.split_once('=') {
+            Some((o, v)) if arg.starts_with("--") => (o, Some(v)),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = || -> Result<&str, String> {
+            inline
+                .or_else(|| args.next().map(|s| s.as_str()))
+                .ok_or(format!("Option {} needs a value", opt))
+        };
+        match opt {
+            "-h" | "--help" => return Ok(Action::Help),
+            "-V" | "--version" => return Ok(Action::Version),
+            "-r" | "--raw" => req.raw = true,
+            "-a" | "--apparent" => req.place = Place::Apparent,
+            "-o" | "--from" => req.observer = value()?.into(),
+            "-d" | "--date" => {
+                req.date = parse::date(value()?).map_err(|e| format!("{}: {}", opt, e))?
+            }
+            "-l" | "--location" => {
+                req.latlong = parse::latlong(value()?).map_err(|e| format!("{}: {}", opt, e))?
+            }
+            "-E" | "--elevation" => {
+                req.elevation = parse::elevation(value()?).map_err(|e| format!("{}: {}", opt, e))?
+            }
+            "-p" | "--pressure" => {
+                req.pressure = parse::pressure(value()?).map_err(|e| format!("{}: {}", opt, e))?
+            }
+            "-t" | "--temperature" => {
+                req.temperature =
+                    parse::temperature(value()?).map_err(|e| format!("{}: {}", opt, e))?
+            }
+            "-m" | "--elements" => req.elements.push(value()?.into()),
+            "-s" | "--satellites" => req.satellites.push(value()?.into()),
+            "-e" | "--ephem" => {
+                req.ephem = Some(parse::ephemq(value()?).map_err(|e| format!("{}: {}", opt, e))?)
+            }
+            "-f" | "--format" => {
+                req.format = parse::format(value()?).map_err(|e| format!("{}: {}", opt, e))?
+            }
+            "--" => positional.extend(args.by_ref().map(|s| s.as_str())),
+            o if o.starts_with('-') && o.len() > 1 => {
+                return Err(format!("Unknown option {}", o));
+            }
+            _ => positional.push(arg),
+        }
+    }
+
+    match positional.as_slice() {
+        ["query", obj, props] => {
+            req.mode = Mode::Query;
+            req.object = obj.to_string();
+            req.properties = props.to_string();
+        }
+        ["query", ..] => return Err("query needs an object and a list of properties".into()),
+        ["phases"] => req.mode = Mode::Phases,
+        ["phases", range] => {
+            req.mode = Mode::Phases;
+            req.range = Some(parse::range(range).map_err(|e| format!("{}: {}", e, range))?);
+        }
+        ["seasons"] => req.mode = Mode::Seasons,
+        ["eclipses"] => req.mode = Mode::Eclipses,
+        ["eclipses", range] => {
+            req.mode = Mode::Eclipses;
+            req.range = Some(parse::range(range).map_err(|e| format!("{}: {}", e, range))?);
+        }
+        ["seasons", year] => {
+            req.mode = Mode::Seasons;
+            req.range = Some(parse::year(year).map_err(|e| format!("{}: {}", e, year))?);
+        }
+        ["occultations"] => req.mode = Mode::Occultations,
+        ["occultations", range] => {
+            req.mode = Mode::Occultations;
+            req.range = Some(parse::range(range).map_err(|e| format!("{}: {}", e, range))?);
+        }
+        ["jovian"] => req.mode = Mode::Jovian,
+        ["jovian", range] => {
+            req.mode = Mode::Jovian;
+            req.range = Some(parse::range(range).map_err(|e| format!("{}: {}", e, range))?);
+        }
+        ["passes", obj] => {
+            req.mode = Mode::Passes;
+            req.object = obj.to_string();
+        }
+        ["passes", obj, range] => {
+            req.mode = Mode::Passes;
+            req.object = obj.to_string();
+            req.range = Some(parse::range(range).map_err(|e| format!("{}: {}", e, range))?);
+        }
+        ["passes", ..] => return Err("passes needs a satellite, and a range".into()),
+        ["conjunctions", objs @ ..] if (1..=3).contains(&objs.len()) => {
+            req.mode = Mode::Conjunctions;
+            // The range is the last argument, if it is a range
+            let objs = match objs.split_last() {
+                Some((last, rest)) if !rest.is_empty() && last.contains(',') => {
+                    if let Ok(r) = parse::range(last) {
+                        req.range = Some(r);
+                        rest
+                    } else {
+                        objs
+                    }
+                }
+                _ => objs,
+            };
+            match objs {
+                [obj] => req.object = obj.to_string(),
+                [obj, other] => {
+                    req.object = obj.to_string();
+                    req.other = other.to_string();
+                }
+                _ => return Err("conjunctions needs one or two objects, and a range".into()),
+            }
+        }
+        ["conjunctions", ..] => {
+            return Err("conjunctions needs one or two objects, and a range".into())
+        }
+        [obj] => req.object = obj.to_string(),
+        // The older "FORMAT OBJECT" form
+        [fmt, obj] => {
+            req.format = parse::format(fmt).map_err(|e| format!("{}: {}", e, fmt))?;
+            req.object = obj.to_string();
+        }
+        [] => return Err("No object specified".into()),
+        _ => return Err("Too many arguments".into()),
+    }
+
+    if req.ephem.is_some() {
+        req.mode = Mode::Ephem;
+        if req.properties.is_empty() {
+            req.properties = request::DEFAULT_EPHEM_PROPERTIES.into();
+        }
+    }
+
+    Ok(Action::Run(Box::new(req)))
+}
+
+/// Parses the command line, and prints the output or a usage error
+pub fn main(args: &[String]) {
+    match parse(args) {
+        Ok(Action::Help) => print!("{}", USAGE),
+        Ok(Action::Version) => println!("cgi-ephem {}", env!("CARGO_PKG_VERSION")),
+        Ok(Action::Run(req)) => match request::run(&req) {
+            Ok(out) => print!("{}", out),
+            Err(e) => {
+                eprintln!("cgi-ephem: {}", e);
+                process::exit(1);
+            }
+        },
+        Err(e) => {
+            eprintln!("cgi-ephem: {}", e);
+            eprintln!("Try 'cgi-ephem --help' for more information.");
+            process::exit(2);
+        }
+    }
+}
@@ -0,0 +1,259 @@
+//! Conjunctions and appulses of two objects, and the oppositions, conjunctions, and elongations of planets
+
+use crate::events;
+use crate::horizon;
+use crate::query::{self, Property};
+use crate::table::Table;
+use crate::value::{AngView, CelObj, Observer, Place, RefFrame, Value};
+use pracstro::{coord::Coord, sol, time};
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Event {
+    /// Closest approach of two objects
+    Conjunction,
+    Opposition,
+    /// Conjunction with the Sun, of a planet further from the Sun than the Earth
+    SolarConjunction,
+    /// Conjunction with the Sun of Mercury or Venus, between the Earth and the Sun
+    InferiorConjunction,
+    /// Conjunction with the Sun of Mercury or Venus, beyond the Sun
+    SuperiorConjunction,
+    /// Greatest elongation east of the Sun, in the evening sky
+    GreatestEastern,
+    /// Greatest elongation west of the Sun, in the morning sky
+    GreatestWestern,
+}
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Event::Conjunction => "Conjunction",
+                Event::Opposition => "Opposition",
+                Event::SolarConjunction => "Solar Conjunction",
+                Event::InferiorConjunction => "Inferior Conjunction",
+                Event::SuperiorConjunction => "Superior Conjunction",
+                Event::GreatestEastern => "Greatest Elongation East",
+                Event::GreatestWestern => "Greatest Elongation West",
+            }
+        )
+    }
+}
+
+fn position(obj: &CelObj, d: time::Date) -> Result<Coord, &'static str> {
+    let Value::Crd(c, _) = query::property_of(
+        obj,
+        Property::Equatorial,
+        &RefFrame {
+            date: d,
+            latlong: None,
+            elevation: 0.0,
+            pressure: horizon::STANDARD_PRESSURE,
+            temperature: horizon::STANDARD_TEMPERATURE,
+            place: Place::Mean,
+            observer: Observer::Earth,
+        },
+    )?
+    else {
+        unreachable!();
+    };
+    Ok(c)
+}
+
+/// Angular separation of two objects, in degrees
+fn separation(a: &CelObj, b: &CelObj, d: time::Date) -> Result<f64, &'static str> {
+    Ok(position(a, d)?.dist(position(b, d)?).degrees())
+}
+
+/// Finds the local minima of `f` in a range, sampled every `step` days
+fn minima(
+    start: time::Date,
+    end: time::Date,
+    step: f64,
+    f: impl Fn(time::Date) -> Result<f64, &'static str>,
+) -> Result<Vec<time::Date>, &'static str> {
+    let n = ((end.julian() - start.julian()) / step).ceil() as usize;
+    let at =
+        |i: usize| time::Date::from_julian((start.julian() + i as f64 * step).min(end.julian()));
+    let samples = (0..=n)
+        .map(|i| f(at(i)))
+        .collect::<Result<Vec<f64>, &'static str>>()?;
+    let mut found = Vec::new();
+    for i in 1..n {
+        if samples[i] <= samples[i - 1] && samples[i] < samples[i + 1] {
+            found.push(events::minimum(at(i - 1), at(i + 1), &f)?);
+        }
+    }
+    Ok(found)
+}
+
+/// Finds every time in a range that `f` crosses zero, in either direction
+fn crossings(
+    start: time::Date,
+    end: time::Date,
+    f: impl Fn(time::Date) -> Result<f64, &'static str>,
+) -> Result<Vec<time::Date>, &'static str> {
+    let mut found = events::roots(start, end, 1.0, &f)?;
+    found.extend(events::roots(start, end, 1.0, |d| Ok(-f(d)?))?);
+    Ok(found)
+}
+
+/// Sampling interval in days, fine enough that the separation can't have two minima between samples
+///
+/// A moon of a planet passes in front of and behind it twice an orbit, so it's sampled 16 times an orbit.
+fn step(a: &CelObj, b: &CelObj) -> f64 {
+    let step = |o: &CelObj| match o {
+        CelObj::Moon => 0.25,
+        CelObj::PlanetMoon(m) => m.period() / 16.0,
+        _ => 1.0,
+    };
+    step(a).min(step(b))
+}
+
+/// Closest approaches further apart than this, in degrees, are the objects turning back without passing
+const MAX_SEPARATION: f64 = 10.0;
+
+/// The times two objects are closest in the sky, with their separation in degrees
+pub fn conjunctions(
+    a: &CelObj,
+    b: &CelObj,
+    start: time::Date,
+    end: time::Date,
+) -> Result<Vec<(time::Date, Event, f64)>, &'static str> {
+    events::check_range(start, end)?;
+    if a == b {
+        return Err("Can't find conjunctions of an object with itself");
+    }
+    if matches!(a, CelObj::Satellite(_)) || matches!(b, CelObj::Satellite(_)) {
+        return Err("Satellites move too quickly to find conjunctions with them");
+    }
+    minima(start, end, step(a, b), |d| separation(a, b, d))?
+        .into_iter()
+        .map(|d| Ok((d, Event::Conjunction, separation(a, b, d)?)))
+        .filter(|r| !matches!(r, Ok((_, _, sep)) if *sep > MAX_SEPARATION))
+        .collect()
+}
+
+/// The oppositions, conjunctions with the Sun, and greatest elongations of a planet, with its elongation in degrees
+pub fn planet_events(
+    obj: &CelObj,
+    start: time::Date,
+    end: time::Date,
+) -> Result<Vec<(time::Date, Event, f64)>, &'static str> {
+    events::check_range(start, end)?;
+    // Minor planets, comets, and the moons of planets are treated as outer planets
+    let inferior = match obj {
+        CelObj::Planet(p) => *p == sol::MERCURY || *p == sol::VENUS,
+        CelObj::Minor(_) | CelObj::PlanetMoon(_) => false,
+        _ => return Err(
+            "Oppositions and elongations are only for planets, give two objects for conjunctions",
+        ),
+    };
+    let elongation = |d: time::Date| separation(obj, &CelObj::Sun, d);
+    // Difference in ecliptic longitude from the Sun, in degrees
+    let lon = |d: time::Date| -> Result<f64, &'static str> {
+        Ok(events::longitude(obj, d)? - events::longitude(&CelObj::Sun, d)?)
+    };
+    let wrap = |x: f64| (x + 180.0).rem_euclid(360.0) - 180.0;
+
+    let mut found = Vec::new();
+    for d in crossings(start, end, |d| Ok(wrap(lon(d)?)))? {
+        let ev = match inferior {
+            true if matches!(obj, CelObj::Planet(p) if p.distance(d) < sol::SUN.distance(d)) => {
+                Event::InferiorConjunction
+            }
+            true => Event::SuperiorConjunction,
+            false => Event::SolarConjunction,
+        };
+        found.push((d, ev, elongation(d)?));
+    }
+    if inferior {
+        for d in minima(start, end, 1.0, |d| Ok(-elongation(d)?))? {
+            let ev = if wrap(lon(d)?) > 0.0 {
+                Event::GreatestEastern
+            } else {
+                Event::GreatestWestern
+            };
+            found.push((d, ev, elongation(d)?));
+        }
+    } else {
+        for d in crossings(start, end, |d| Ok(wrap(lon(d)? - 180.0)))? {
+            found.push((d, Event::Opposition, elongation(d)?));
+        }
+    }
+    found.sort_by(|a, b| a.0.julian().total_cmp(&b.0.julian()));
+    Ok(found)
+}
+
+/// A table of the conjunctions of two objects, or of the events of a planet if there is no second object
+pub fn table(
+    a: &CelObj,
+    b: Option<&CelObj>,
+    start: time::Date,
+    end: time::Date,
+) -> Result<Table, &'static str> {
+    let found = match b {
+        Some(b) => conjunctions(a, b, start, end)?,
+        None => planet_events(a, start, end)?,
+    };
+    Ok(Table {
+        columns: vec![
+            "Date".into(),
+            "Event".into(),
+            match b {
+                Some(_) => "Separation".into(),
+                None => "Elongation".into(),
+            },
+        ],
+        rows: found
+            .into_iter()
+            .map(|(d, e, sep)| {
+                vec![
+                    Value::Date(d),
+                    Value::Str(e.to_string()),
+                    Value::Ang(time::Angle::from_degrees(sep), AngView::Angle),
+                ]
+            })
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moons::Moon;
+
+    #[test]
+    fn moon_of_planet() {
+        // Io passes in front of or behind Jupiter every 0.885 days
+        let found = conjunctions(
+            &CelObj::PlanetMoon(Moon::Io),
+            &CelObj::Planet(sol::JUPITER),
+            time::Date::from_julian(2461330.5),
+            time::Date::from_julian(2461337.5),
+        )
+        .unwrap();
+        assert_eq!(found.len(), 8);
+        for w in found.windows(2) {
+            assert!((w[1].0.julian() - w[0].0.julian() - 0.885).abs() < 0.03);
+        }
+    }
+
+    #[test]
+    fn mars_opposition_2025() {
+        let found = planet_events(
+            &CelObj::Planet(sol::MARS),
+            time::Date::from_julian(2460645.5),
+            time::Date::from_julian(2460734.5),
+        )
+        .unwrap();
+        assert_eq!(found.len(), 1);
+        // 2025-01-16 02:38 UT
+        let (d, ev, elongation) = found[0];
+        assert_eq!(ev, Event::Opposition);
+        assert!((d.julian() - 2460691.6097).abs() < 0.005, "{}", d.julian());
+        assert!(elongation > 175.0 && elongation < 176.0, "{}", elongation);
+    }
+}
@@ -0,0 +1,404 @@
+//! Solar and lunar eclipses
+//!
+//! Eclipses are found at new and full moons, with the geometry of the shadows at greatest eclipse
+//! (the least separation of the Moon from the Sun, or from the center of the Earth's shadow), following
+//! the conventions of Meeus (Astronomical Algorithms, ch. 54).
+
+use crate::events::{self, LunarPhase};
+use crate::horizon::{self, EARTH_RADIUS};
+use crate::lunar;
+use crate::table::Table;
+use crate::value::{CelObj, NoEvent, RefFrame, Value};
+use pracstro::{coord::Coord, sol, time};
+use std::fmt;
+
+const AU: f64 = 149597870.7;
+const SUN_RADIUS: f64 = 696000.0;
+const MOON_RADIUS: f64 = 1737.4;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    Total,
+    Annular,
+    /// Total along part of the path, and annular along the rest
+    Hybrid,
+    Partial,
+    Penumbral,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Eclipse {
+    pub solar: bool,
+    pub kind: Kind,
+    /// Time of greatest eclipse
+    pub greatest: time::Date,
+    /// The fraction of the Sun's diameter covered (or the ratio of diameters for central eclipses), or the
+    /// fraction of the Moon's diameter in the umbra (or the penumbra for penumbral eclipses)
+    pub magnitude: f64,
+    /// Least distance of the Moon's shadow axis from the center of the Earth (or of the Moon from the axis
+    /// of the Earth's shadow), in Earth radii, positive to the north
+    pub gamma: f64,
+    /// Times the eclipse can be seen from a location
+    pub visible: Option<(time::Date, time::Date)>,
+}
+impl fmt::Display for Eclipse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {}",
+            match self.kind {
+                Kind::Total => "Total",
+                Kind::Annular => "Annular",
+                Kind::Hybrid => "Hybrid",
+                Kind::Partial => "Partial",
+                Kind::Penumbral => "Penumbral",
+            },
+            if self.solar { "Solar" } else { "Lunar" }
+        )
+    }
+}
+
+/// Apparent position of the Sun (with aberration, which is the same for the Moon's shadow) and distance in km
+fn sun(d: time::Date) -> (Coord, f64) {
+    let r = sol::SUN.distance(d);
+    let (lambda, beta) = sol::SUN
+        .location(d)
+        .precess(time::Date::from_julian(2451545.0), d)
+        .ecliptic(d);
+    (
+        Coord::from_ecliptic(
+            lambda - time::Angle::from_degrees(20.4898 / 3600.0 / r),
+            beta,
+            d,
+        ),
+        r * AU,
+    )
+}
+
+/// Position of the Moon, and distance in km
+fn moon(d: time::Date) -> (Coord, f64) {
+    (lunar::location(d), lunar::distance(d) * AU)
+}
+
+/// The point in the sky opposite a position
+fn antipode(c: Coord) -> Coord {
+    let (ra, de) = c.equatorial();
+    Coord::from_equatorial(
+        ra + time::Angle::from_degrees(180.0),
+        time::Angle::from_degrees(-de.to_latitude().degrees()),
+    )
+}
+
+/// The sign of gamma, positive if the Moon is north of the Sun or the shadow
+fn north(m: Coord, of: Coord) -> f64 {
+    if m.equatorial().1.to_latitude().degrees() >= of.equatorial().1.to_latitude().degrees() {
+        1.0
+    } else {
+        -1.0
+    }
+}
+
+/// The first time from `from` towards `to` that `f` is no longer negative, if there is one
+fn edge(from: time::Date, to: time::Date, f: impl Fn(time::Date) -> f64) -> Option<time::Date> {
+    if f(to) < 0.0 {
+        return None;
+    }
+    let (mut a, mut b) = (from.julian(), to.julian());
+    for _ in 0..30 {
+        let mid = (a + b) / 2.0;
+        if f(time::Date::from_julian(mid)) < 0.0 {
+            a = mid;
+        } else {
+            b = mid;
+        }
+    }
+    Some(time::Date::from_julian((a + b) / 2.0))
+}
+
+/// The part of a span of time that an object is above the horizon
+fn visible_part(
+    obj: &CelObj,
+    (start, end): (time::Date, time::Date),
+    observer: &RefFrame,
+) -> Option<(time::Date, time::Date)> {
+    let (lat, long) = observer.latlong?;
+    let above = |d: time::Date| {
+        let c = match obj {
+            CelObj::Moon => lunar::location(d),
+            _ => sun(d).0,
+        };
+        let rf = RefFrame {
+            date: d,
+            ..*observer
+        };
+        horizon::altaz(c, d, lat, long).1.to_latitude().degrees()
+            - horizon::standard_altitude(obj, &rf)
+    };
+    // Sampled every few minutes, the eclipse is taken to be seen from the first to the last sample above the horizon
+    const SAMPLES: usize = 100;
+    let at = |i: usize| {
+        time::Date::from_julian(
+            start.julian() + (end.julian() - start.julian()) * (i as f64) / (SAMPLES as f64),
+        )
+    };
+    let first = (0..=SAMPLES).find(|i| above(at(*i)) >= 0.0)?;
+    let last = (0..=SAMPLES).rev().find(|i| above(at(*i)) >= 0.0)?;
+    let rise = if first == 0 {
+        at(0)
+    } else {
+        edge(at(first - 1), at(first), above)?
+    };
+    let set = if last == SAMPLES {
+        at(SAMPLES)
+    } else {
+        edge(at(last + 1), at(last), above)?
+    };
+    Some((rise, set))
+}
+
+/// The solar eclipse around a new moon, if there is one
+fn solar(new: time::Date, observer: &RefFrame) -> Result<Option<Eclipse>, &'static str> {
+    let sep = |d: time::Date| moon(d).0.dist(sun(d).0).radians();
+    let greatest = events::minimum(
+        time::Date::from_julian(new.julian() - 0.5),
+        time::Date::from_julian(new.julian() + 0.5),
+        |d| Ok(sep(d)),
+    )?;
+    let ((m, dm), (s, ds)) = (moon(greatest), sun(greatest));
+
+    let gamma = north(m, s) * dm * sep(greatest).sin() / EARTH_RADIUS;
+    // Radii of the umbra (negative when the umbra reaches the Earth) and penumbra, in the plane through the
+    // center of the Earth, in Earth radii
+    let u = (dm * (SUN_RADIUS - MOON_RADIUS) / (ds - dm) - MOON_RADIUS) / EARTH_RADIUS;
+    let p = (MOON_RADIUS + dm * (SUN_RADIUS + MOON_RADIUS) / (ds - dm)) / EARTH_RADIUS;
+    // The polar radius of the Earth, since the shadow mostly touches at high latitudes
+    const POLAR: f64 = 0.9972;
+
+    let g = gamma.abs();
+    if g > POLAR + p {
+        return Ok(None);
+    }
+    let (kind, magnitude) = if g > POLAR + u.abs() {
+        (Kind::Partial, (POLAR + p - g) / (p + u))
+    } else {
+        // The ratio of the diameters, seen from where the axis meets the Earth
+        let near = dm - EARTH_RADIUS * (1.0 - g * g).max(0.0).sqrt();
+        let ratio = (MOON_RADIUS / near) / (SUN_RADIUS / ds);
+        let kind = if u < 0.0 {
+            Kind::Total
+        } else if u < 0.00464 * (1.0 - g * g).max(0.0).sqrt() {
+            Kind::Hybrid
+        } else {
+            Kind::Annular
+        };
+        (kind, ratio)
+    };
+
+    // Local circumstances, with the Moon's position seen from the observer
+    let visible = observer.latlong.and_then(|(lat, long)| {
+        let overlap = |d: time::Date| {
+            let (mt, dmt) = {
+                let (m, dm) = moon(d);
+                horizon::topocentric(m, dm / AU, d, lat, long, observer.elevation)
+            };
+            let (s, ds) = sun(d);
+            (MOON_RADIUS / (dmt * AU)).asin() + (SUN_RADIUS / ds).asin() - mt.dist(s).radians()
+        };
+        let local = events::minimum(
+            time::Date::from_julian(greatest.julian() - 0.25),
+            time::Date::from_julian(greatest.julian() + 0.25),
+            |d| Ok(-overlap(d)),
+        )
+        .ok()?;
+        if overlap(local) <= 0.0 {
+            return None;
+        }
+        let start = edge(local, time::Date::from_julian(local.julian() - 0.25), |d| {
+            -overlap(d)
+        })?;
+        let end = edge(local, time::Date::from_julian(local.julian() + 0.25), |d| {
+            -overlap(d)
+        })?;
+        visible_part(&CelObj::Sun, (start, end), observer)
+    });
+
+    Ok(Some(Eclipse {
+        solar: true,
+        kind,
+        greatest,
+        magnitude,
+        gamma,
+        visible,
+    }))
+}
+
+/// The lunar eclipse around a full moon, if there is one
+fn lunar(full: time::Date, observer: &RefFrame) -> Result<Option<Eclipse>, &'static str> {
+    let sep = |d: time::Date| moon(d).0.dist(antipode(sun(d).0)).radians();
+    let greatest = events::minimum(
+        time::Date::from_julian(full.julian() - 0.5),
+        time::Date::from_julian(full.julian() + 0.5),
+        |d| Ok(sep(d)),
+    )?;
+
+    // Radii of the umbra and penumbra, and the Moon's semidiameter, as angles at the Moon's distance
+    let radii = |d: time::Date| {
+        let ((_, dm), (_, ds)) = (moon(d), sun(d));
+        let (pm, ps) = ((EARTH_RADIUS / dm).asin(), (EARTH_RADIUS / ds).asin());
+        let ss = (SUN_RADIUS / ds).asin();
+        // Enlarged by 2% for the Earth's atmosphere, as is traditional
+        (
+            1.02 * (pm + ps - ss),
+            1.02 * (pm + ps + ss),
+            (MOON_RADIUS / dm).asin(),
+        )
+    };
+    let (m, dm) = moon(greatest);
+    let d = sep(greatest);
+    let (umbra, penumbra, sm) = radii(greatest);
+    let gamma = north(m, antipode(sun(greatest).0)) * dm * d.sin() / EARTH_RADIUS;
+
+    let umbral = (umbra + sm - d) / (2.0 * sm);
+    let penumbral = (penumbra + sm - d) / (2.0 * sm);
+    let (kind, magnitude) = match (umbral, penumbral) {
+        (u, _) if u >= 1.0 => (Kind::Total, u),
+        (u, _) if u > 0.0 => (Kind::Partial, u),
+        (_, p) if p > 0.0 => (Kind::Penumbral, p),
+        _ => return Ok(None),
+    };
+
+    // From first to last contact with the umbra, or the penumbra for penumbral eclipses
+    let touching = |d: time::Date| {
+        let (umbra, penumbra, sm) = radii(d);
+        sep(d)
+            - sm
+            - if kind == Kind::Penumbral {
+                penumbra
+            } else {
+                umbra
+            }
+    };
+    let visible = match (
+        edge(
+            greatest,
+            time::Date::from_julian(greatest.julian() - 0.3),
+            touching,
+        ),
+        edge(
+            greatest,
+            time::Date::from_julian(greatest.julian() + 0.3),
+            touching,
+        ),
+    ) {
+        (Some(start), Some(end)) => visible_part(&CelObj::Moon, (start, end), observer),
+        _ => None,
+    };
+
+    Ok(Some(Eclipse {
+        solar: false,
+        kind,
+        greatest,
+        magnitude,
+        gamma,
+        visible,
+    }))
+}
+
+/// The eclipses in a range, in order, with when they can be seen from a location
+///
+/// Only the location, elevation, and weather of the observer are used, not its date.
+pub fn eclipses(
+    start: time::Date,
+    end: time::Date,
+    observer: &RefFrame,
+) -> Result<Vec<Eclipse>, &'static str> {
+    let mut found = Vec::new();
+    for (d, phase, _) in events::lunar_phases(start, end)? {
+        let e = match phase {
+            LunarPhase::New => solar(d, observer)?,
+            LunarPhase::Full => lunar(d, observer)?,
+            _ => None,
+        };
+        found.extend(e);
+    }
+    Ok(found)
+}
+
+/// A table of the eclipses in a range, with columns for when they can be seen if a location is given
+pub fn table(
+    start: time::Date,
+    end: time::Date,
+    observer: &RefFrame,
+) -> Result<Table, &'static str> {
+    let mut columns: Vec<String> = vec![
+        "Date".into(),
+        "Eclipse".into(),
+        "Magnitude".into(),
+        "Gamma".into(),
+    ];
+    if observer.latlong.is_some() {
+        columns.extend(["Visible From".into(), "Visible Until".into()]);
+    }
+    Ok(Table {
+        columns,
+        rows: eclipses(start, end, observer)?
+            .into_iter()
+            .map(|e| {
+                let mut row = vec![
+                    Value::Date(e.greatest),
+                    Value::Str(e.to_string()),
+                    Value::Num(e.magnitude),
+                    Value::Num(e.gamma),
+                ];
+                if observer.latlong.is_some() {
+                    row.extend(match e.visible {
+                        Some((from, until)) => [Value::RsTime(Ok(from)), Value::RsTime(Ok(until))],
+                        None => [
+                            Value::RsTime(Err(NoEvent::NotOnDay)),
+                            Value::RsTime(Err(NoEvent::NotOnDay)),
+                        ],
+                    });
+                }
+                row
+            })
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::{Observer, Place};
+
+    #[test]
+    fn total_solar_2024() {
+        let rf = RefFrame {
+            latlong: None,
+            elevation: 0.0,
+            pressure: 1010.0,
+            temperature: 10.0,
+            place: Place::Mean,
+            observer: Observer::Earth,
+            date: time::Date::from_julian(2460401.5),
+        };
+        let found = eclipses(
+            time::Date::from_julian(2460401.5),
+            time::Date::from_julian(2460431.5),
+            &rf,
+        )
+        .unwrap();
+        assert_eq!(found.len(), 1);
+        // NASA gives greatest eclipse at 18:17:16 UT, magnitude 1.0566, and gamma 0.3431
+        let e = &found[0];
+        assert!(e.solar);
+        assert_eq!(e.kind, Kind::Total);
+        assert!(
+            (e.greatest.julian() - 2460409.26199).abs() < 0.002,
+            "{}",
+            e.greatest.julian()
+        );
+        assert!((e.magnitude - 1.0566).abs() < 0.005, "{}", e.magnitude);
+        assert!((e.gamma - 0.3431).abs() < 0.01, "{}", e.gamma);
+        assert_eq!(e.visible, None);
+    }
+}
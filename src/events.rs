@@ -0,0 +1,282 @@
+//! Searches for the times of events over a range of dates, such as the phases of the Moon and the seasons
+
+use crate::query::{self, Property};
+use crate::table::Table;
+use crate::value::{CelObj, Observer, Place, RefFrame, Value};
+use crate::{apparent, horizon};
+use pracstro::time;
+use std::fmt;
+
+/// Mean length of a lunation, in days
+pub const SYNODIC_MONTH: f64 = 29.530588861;
+
+/// The longest range that can be searched, in days
+pub const MAX_RANGE: f64 = 100.0 * 365.25;
+
+/// The principal phases of the Moon
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LunarPhase {
+    New,
+    FirstQuarter,
+    Full,
+    LastQuarter,
+}
+impl LunarPhase {
+    const ALL: [LunarPhase; 4] = [
+        LunarPhase::New,
+        LunarPhase::FirstQuarter,
+        LunarPhase::Full,
+        LunarPhase::LastQuarter,
+    ];
+
+    /// Quarters of a lunation since the new moon
+    fn quarter(self) -> f64 {
+        match self {
+            LunarPhase::New => 0.0,
+            LunarPhase::FirstQuarter => 1.0,
+            LunarPhase::Full => 2.0,
+            LunarPhase::LastQuarter => 3.0,
+        }
+    }
+}
+impl fmt::Display for LunarPhase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                LunarPhase::New => "New Moon",
+                LunarPhase::FirstQuarter => "First Quarter",
+                LunarPhase::Full => "Full Moon",
+                LunarPhase::LastQuarter => "Last Quarter",
+            }
+        )
+    }
+}
+
+/// The equinoxes and solstices, when the Sun's apparent longitude is a multiple of 90°
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Season {
+    MarchEquinox,
+    JuneSolstice,
+    SeptemberEquinox,
+    DecemberSolstice,
+}
+impl Season {
+    const ALL: [Season; 4] = [
+        Season::MarchEquinox,
+        Season::JuneSolstice,
+        Season::SeptemberEquinox,
+        Season::DecemberSolstice,
+    ];
+
+    /// The Sun's apparent longitude, in degrees
+    fn longitude(self) -> f64 {
+        match self {
+            Season::MarchEquinox => 0.0,
+            Season::JuneSolstice => 90.0,
+            Season::SeptemberEquinox => 180.0,
+            Season::DecemberSolstice => 270.0,
+        }
+    }
+}
+impl fmt::Display for Season {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Season::MarchEquinox => "March Equinox",
+                Season::JuneSolstice => "June Solstice",
+                Season::SeptemberEquinox => "September Equinox",
+                Season::DecemberSolstice => "December Solstice",
+            }
+        )
+    }
+}
+
+/// The start and end of a calendar year
+pub fn year(y: i64) -> (time::Date, time::Date) {
+    (
+        time::Date::from_calendar(y, 1, 1, time::Angle::from_degrees(0.0)),
+        time::Date::from_calendar(y + 1, 1, 1, time::Angle::from_degrees(0.0)),
+    )
+}
+
+/// Checks that a range can be searched
+pub fn check_range(start: time::Date, end: time::Date) -> Result<(), &'static str> {
+    if end.julian() < start.julian() {
+        Err("End of range before start")
+    } else if end.julian() - start.julian() > MAX_RANGE {
+        Err("Ranges are limited to 100 years")
+    } else {
+        Ok(())
+    }
+}
+
+/// Wraps an angle in degrees into (-180, 180]
+fn wrap(deg: f64) -> f64 {
+    let d = deg.rem_euclid(360.0);
+    if d > 180.0 {
+        d - 360.0
+    } else {
+        d
+    }
+}
+
+/// Finds every time in a range that `f` goes from negative to positive
+///
+/// `f` is sampled every `step` days, which must be short enough that it can't cross twice between
+/// samples. Jumps of more than 90 between samples are taken to be an angle wrapping around, and ignored.
+pub fn roots(
+    start: time::Date,
+    end: time::Date,
+    step: f64,
+    f: impl Fn(time::Date) -> Result<f64, &'static str>,
+) -> Result<Vec<time::Date>, &'static str> {
+    let at = |jd: f64| f(time::Date::from_julian(jd));
+    let mut found = Vec::new();
+    let (mut lo, mut flo) = (start.julian(), at(start.julian())?);
+    while lo < end.julian() {
+        let hi = (lo + step).min(end.julian());
+        let fhi = at(hi)?;
+        if flo < 0.0 && fhi >= 0.0 && fhi - flo < 90.0 {
+            let (mut a, mut b) = (lo, hi);
+            // A day / 2^30 is well under a millisecond
+            for _ in 0..30 {
+                let mid = (a + b) / 2.0;
+                if at(mid)? < 0.0 {
+                    a = mid;
+                } else {
+                    b = mid;
+                }
+            }
+            found.push(time::Date::from_julian((a + b) / 2.0));
+        }
+        (lo, flo) = (hi, fhi);
+    }
+    Ok(found)
+}
+
+/// Finds the time `f` is least between two dates, by golden section search
+///
+/// `f` must have a single minimum in the range.
+pub fn minimum(
+    start: time::Date,
+    end: time::Date,
+    f: impl Fn(time::Date) -> Result<f64, &'static str>,
+) -> Result<time::Date, &'static str> {
+    let at = |jd: f64| f(time::Date::from_julian(jd));
+    let r = (5.0_f64.sqrt() - 1.0) / 2.0;
+    let (mut a, mut b) = (start.julian(), end.julian());
+    let (mut c, mut d) = (b - r * (b - a), a + r * (b - a));
+    let (mut fc, mut fd) = (at(c)?, at(d)?);
+    // A day * 0.618^50 is well under a millisecond
+    for _ in 0..50 {
+        if fc < fd {
+            (b, d, fd) = (d, c, fc);
+            c = b - r * (b - a);
+            fc = at(c)?;
+        } else {
+            (a, c, fc) = (c, d, fd);
+            d = a + r * (b - a);
+            fd = at(d)?;
+        }
+    }
+    Ok(time::Date::from_julian((a + b) / 2.0))
+}
+
+/// Geocentric ecliptic longitude of an object, in degrees
+pub fn longitude(obj: &CelObj, d: time::Date) -> Result<f64, &'static str> {
+    let Value::Crd(c, _) = query::property_of(
+        obj,
+        Property::Equatorial,
+        &RefFrame {
+            date: d,
+            latlong: None,
+            elevation: 0.0,
+            pressure: horizon::STANDARD_PRESSURE,
+            temperature: horizon::STANDARD_TEMPERATURE,
+            place: Place::Mean,
+            observer: Observer::Earth,
+        },
+    )?
+    else {
+        unreachable!();
+    };
+    Ok(c.ecliptic(d).0.degrees())
+}
+
+/// Apparent ecliptic longitude of the Sun, in degrees, with nutation and aberration
+pub fn apparent_sun_longitude(d: time::Date) -> Result<f64, &'static str> {
+    let r = pracstro::sol::SUN.distance(d);
+    Ok(longitude(&CelObj::Sun, d)? + apparent::nutation(d).0 - 20.4898 / 3600.0 / r)
+}
+
+/// The Brown Lunation Number of the lunation containing a phase, lunation 1 began on 1923-01-16
+fn lunation(d: time::Date, phase: LunarPhase) -> i64 {
+    // Meeus counts lunations from the new moon of 2000-01-06
+    ((d.julian() - 2451550.09766) / SYNODIC_MONTH - phase.quarter() / 4.0).round() as i64 + 953
+}
+
+/// The principal phases of the Moon in a range, in order, with their lunation numbers
+///
+/// Phases are when the difference in the ecliptic longitudes of the Moon and the Sun is a multiple of 90°.
+pub fn lunar_phases(
+    start: time::Date,
+    end: time::Date,
+) -> Result<Vec<(time::Date, LunarPhase, i64)>, &'static str> {
+    check_range(start, end)?;
+    let mut found = Vec::new();
+    for phase in LunarPhase::ALL {
+        for d in roots(start, end, 1.0, |d| {
+            Ok(wrap(
+                longitude(&CelObj::Moon, d)? - longitude(&CelObj::Sun, d)? - phase.quarter() * 90.0,
+            ))
+        })? {
+            found.push((d, phase, lunation(d, phase)));
+        }
+    }
+    found.sort_by(|a, b| a.0.julian().total_cmp(&b.0.julian()));
+    Ok(found)
+}
+
+/// A table of the phases of the Moon in a range
+pub fn lunar_phase_table(start: time::Date, end: time::Date) -> Result<Table, &'static str> {
+    Ok(Table {
+        columns: vec!["Date".into(), "Phase".into(), "Lunation".into()],
+        rows: lunar_phases(start, end)?
+            .into_iter()
+            .map(|(d, p, n)| vec![Value::Date(d), Value::Str(p.to_string()), Value::Int(n)])
+            .collect(),
+    })
+}
+
+/// The equinoxes and solstices in a range, in order
+pub fn seasons(
+    start: time::Date,
+    end: time::Date,
+) -> Result<Vec<(time::Date, Season)>, &'static str> {
+    check_range(start, end)?;
+    let mut found = Vec::new();
+    for season in Season::ALL {
+        for d in roots(start, end, 1.0, |d| {
+            Ok(wrap(apparent_sun_longitude(d)? - season.longitude()))
+        })? {
+            found.push((d, season));
+        }
+    }
+    found.sort_by(|a, b| a.0.julian().total_cmp(&b.0.julian()));
+    Ok(found)
+}
+
+/// A table of the equinoxes and solstices in a range
+pub fn season_table(start: time::Date, end: time::Date) -> Result<Table, &'static str> {
+    Ok(Table {
+        columns: vec!["Date".into(), "Event".into()],
+        rows: seasons(start, end)?
+            .into_iter()
+            .map(|(d, s)| vec![Value::Date(d), Value::Str(s.to_string())])
+            .collect(),
+    })
+}
@@ -0,0 +1,61 @@
+//! Galactic and supergalactic coordinates
+//!
+//! Galactic coordinates have the plane of the Milky Way as their equator, with longitude from the direction of
+//! the galactic center. Supergalactic coordinates do the same with the plane of the Local Supercluster, with
+//! longitude from where it crosses the galactic plane. Both are fixed to the stars, so positions are precessed
+//! back to J2000 first.
+
+use pracstro::{coord::Coord, time};
+
+/// North galactic pole in J2000 equatorial coordinates, in degrees
+const GALACTIC_POLE: (f64, f64) = (192.85948, 27.12825);
+
+/// Galactic longitude of the north celestial pole, in degrees
+const GALACTIC_NCP: f64 = 122.93192;
+
+/// North supergalactic pole in galactic coordinates, in degrees
+const SUPERGALACTIC_POLE: (f64, f64) = (47.37, 6.32);
+
+/// Supergalactic longitude of the north galactic pole, in degrees
+///
+/// Supergalactic longitude starts at l = 137.37°, where the two planes cross, which is 90° from here.
+const SUPERGALACTIC_NGP: f64 = 90.0;
+
+/// Longitude and latitude in a frame with its pole at `pole`, and `zero` the longitude of the old pole
+fn rotate(
+    (lon, lat): (time::Angle, time::Angle),
+    pole: (f64, f64),
+    zero: f64,
+) -> (time::Angle, time::Angle) {
+    let (plon, plat) = (
+        time::Angle::from_degrees(pole.0),
+        time::Angle::from_degrees(pole.1),
+    );
+    let dlon = lon - plon;
+    let b = time::Angle::asin(lat.sin() * plat.sin() + lat.cos() * plat.cos() * dlon.cos());
+    let l = time::Angle::from_degrees(zero)
+        - time::Angle::atan2(
+            lat.cos() * dlon.sin(),
+            lat.sin() * plat.cos() - lat.cos() * plat.sin() * dlon.cos(),
+        );
+    (l, b)
+}
+
+/// Galactic longitude and latitude of a position referred to the equator and equinox of a date
+pub fn galactic(c: Coord, d: time::Date) -> (time::Angle, time::Angle) {
+    let (ra, de) = c
+        .precess(d, time::Date::from_julian(2451545.0))
+        .equatorial();
+    rotate((ra, de), GALACTIC_POLE, GALACTIC_NCP)
+}
+
+/// J2000 equatorial coordinates of a galactic longitude and latitude
+pub fn from_galactic(l: time::Angle, b: time::Angle) -> Coord {
+    let (ra, de) = rotate((l, b), (GALACTIC_NCP, GALACTIC_POLE.1), GALACTIC_POLE.0);
+    Coord::from_equatorial(ra, de)
+}
+
+/// Supergalactic longitude and latitude of a position referred to the equator and equinox of a date
+pub fn supergalactic(c: Coord, d: time::Date) -> (time::Angle, time::Angle) {
+    rotate(galactic(c, d), SUPERGALACTIC_POLE, SUPERGALACTIC_NGP)
+}
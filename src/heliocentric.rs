@@ -0,0 +1,155 @@
+//! Positions as vectors from the Sun, for seeing objects from somewhere other than the Earth
+//!
+//! Vectors are in AU, along the axes of the J2000 equator, as [`sol::Planet::locationcart()`] is. They're
+//! geometric, without light-time, as pracstro's positions of the planets are. Stars and raw coordinates are far
+//! enough away to be in the same direction from anywhere in the solar system, so they don't have vectors.
+
+use crate::query::{self, Property};
+use crate::value::{CelObj, Observer, RefFrame, Value};
+use crate::{lunar, moons, satellite};
+use pracstro::{coord::Coord, sol, time};
+
+type Vector = (f64, f64, f64);
+
+/// Angular diameter of the Earth at 1 AU, in degrees, which pracstro doesn't have (it's 12742 km across)
+const EARTH_THETA0: f64 = 17.57 / 3600.0;
+
+/// Obliquity of the J2000 ecliptic, in degrees
+pub const OBLIQUITY_J2000: f64 = 23.43928;
+
+fn sub(a: Vector, b: Vector) -> Vector {
+    (a.0 - b.0, a.1 - b.1, a.2 - b.2)
+}
+
+fn dot(a: Vector, b: Vector) -> f64 {
+    a.0 * b.0 + a.1 * b.1 + a.2 * b.2
+}
+
+fn cross(a: Vector, b: Vector) -> Vector {
+    (
+        a.1 * b.2 - a.2 * b.1,
+        a.2 * b.0 - a.0 * b.2,
+        a.0 * b.1 - a.1 * b.0,
+    )
+}
+
+fn norm(a: Vector) -> f64 {
+    dot(a, a).sqrt()
+}
+
+fn j2000() -> time::Date {
+    time::Date::from_julian(2451545.0)
+}
+
+/// Position of something near the Earth, from its geocentric position referred to the equinox of date
+///
+/// [`Coord::cartesian()`] takes the right ascension as if it were an angle from the pole, so this doesn't use it.
+fn near_earth(c: Coord, dist: f64, d: time::Date) -> Vector {
+    let e = sol::EARTH.locationcart(d);
+    let (ra, de) = c.precess(d, j2000()).equatorial();
+    let (ra, de) = (ra.radians(), de.to_latitude().radians());
+    (
+        e.0 + dist * de.cos() * ra.cos(),
+        e.1 + dist * de.cos() * ra.sin(),
+        e.2 + dist * de.sin(),
+    )
+}
+
+/// Position of an object
+pub fn position(obj: &CelObj, d: time::Date) -> Result<Vector, &'static str> {
+    Ok(match obj {
+        CelObj::Sun => (0.0, 0.0, 0.0),
+        CelObj::Planet(p) => p.locationcart(d),
+        CelObj::Minor(o) => o.locationcart(d),
+        CelObj::Moon => near_earth(lunar::location(d), lunar::distance(d), d),
+        CelObj::Satellite(s) => {
+            let (c, dist) = satellite::geocentric(s, d)?;
+            near_earth(c, dist, d)
+        }
+        CelObj::PlanetMoon(m) => moons::heliocentric(*m, d),
+        CelObj::Star(_) | CelObj::Crd(..) => {
+            return Err("Stars don't have a position in the solar system")
+        }
+    })
+}
+
+/// Position of an observer
+pub fn observer(o: Observer, d: time::Date) -> Vector {
+    match o {
+        Observer::Earth => sol::EARTH.locationcart(d),
+        Observer::Sun => (0.0, 0.0, 0.0),
+        Observer::Moon => near_earth(lunar::location(d), lunar::distance(d), d),
+        Observer::Planet(p) => p.locationcart(d),
+        Observer::PlanetMoon(m) => moons::heliocentric(m, d),
+        Observer::Point(x, y, z) => {
+            let eps = OBLIQUITY_J2000.to_radians();
+            (
+                x,
+                eps.cos() * y - eps.sin() * z,
+                eps.sin() * y + eps.cos() * z,
+            )
+        }
+    }
+}
+
+/// Vector from the observer to an object
+fn relative(obj: &CelObj, rf: &RefFrame) -> Result<Vector, &'static str> {
+    let v = sub(position(obj, rf.date)?, observer(rf.observer, rf.date));
+    // About 150 m
+    if norm(v) < 1e-9 {
+        return Err("Can't see an object from itself");
+    }
+    Ok(v)
+}
+
+/// Position seen by the observer, referred to the mean equator and equinox of date
+pub fn location(obj: &CelObj, rf: &RefFrame) -> Result<Coord, &'static str> {
+    let (x, y, z) = relative(obj, rf)?;
+    Ok(Coord::from_cartesian(x, y, z).precess(j2000(), rf.date))
+}
+
+/// Distance from the observer, in AU
+pub fn distance(obj: &CelObj, rf: &RefFrame) -> Result<f64, &'static str> {
+    Ok(norm(relative(obj, rf)?))
+}
+
+/// Angular diameter seen by the observer, scaled from what it is seen from the Earth
+pub fn angdia(obj: &CelObj, rf: &RefFrame) -> Result<time::Angle, &'static str> {
+    let dist = distance(obj, rf)?;
+    if *obj == CelObj::Planet(sol::EARTH) {
+        return Ok(time::Angle::from_degrees(EARTH_THETA0) / dist);
+    }
+    let earth = RefFrame {
+        observer: Observer::Earth,
+        ..*rf
+    };
+    let (Value::Ang(angdia, _), Value::Dist(from_earth)) = (
+        query::property_of(obj, Property::AngDia, &earth)?,
+        query::property_of(obj, Property::Distance, &earth)?,
+    ) else {
+        unreachable!();
+    };
+    Ok(angdia / (dist / from_earth))
+}
+
+/// Phase seen by the observer, measured as the Moon's is, from 0° at new to 180° at full
+///
+/// It's waxing while the object is east of the Sun, going around the Sun counterclockwise seen from north of
+/// the ecliptic.
+pub fn phaseangle(obj: &CelObj, rf: &RefFrame) -> Result<time::Angle, &'static str> {
+    let p = position(obj, rf.date)?;
+    let v = relative(obj, rf)?;
+    // The angle at the object between the Sun and the observer, which is 0° when it's fully lit
+    let i = (dot(p, v) / (norm(p) * norm(v)))
+        .clamp(-1.0, 1.0)
+        .acos()
+        .to_degrees();
+    let sun = sub((0.0, 0.0, 0.0), observer(rf.observer, rf.date));
+    let eps = OBLIQUITY_J2000.to_radians();
+    let east = dot(cross(sun, v), (0.0, -eps.sin(), eps.cos())) > 0.0;
+    Ok(time::Angle::from_degrees(if east {
+        180.0 - i
+    } else {
+        180.0 + i
+    }))
+}
@@ -0,0 +1,400 @@
+//! Positions relative to the observer's horizon, and the times objects cross it
+
+use crate::lunar;
+use crate::query::{self, Property};
+use crate::value::{CelObj, NoEvent, RefFrame, Value};
+use pracstro::{coord::Coord, moon, time};
+
+/// Greenwich mean sidereal time
+///
+/// [`time::Angle::gst()`] takes the centuries at the instant rather than at 0h, which counts the sidereal
+/// day's extra rotation twice, this is the IAU 1982 expression from Meeus (12.4).
+pub fn gmst(d: time::Date) -> time::Angle {
+    let t = d.centuries();
+    time::Angle::from_degrees(
+        280.46061837 + 360.98564736629 * (d.julian() - 2451545.0) + 0.000387933 * t * t
+            - t * t * t / 38710000.0,
+    )
+}
+
+/// Hour angle of a coordinate, west of the meridian is positive
+pub fn hour_angle(c: Coord, d: time::Date, long: time::Angle) -> time::Angle {
+    gmst(d) + long - c.equatorial().0
+}
+
+/// Azimuth (from north, through east) and altitude, in the same order as [`Coord::horizon()`]
+pub fn altaz(
+    c: Coord,
+    d: time::Date,
+    lat: time::Angle,
+    long: time::Angle,
+) -> (time::Angle, time::Angle) {
+    let de = c.equatorial().1;
+    let ha = hour_angle(c, d, long);
+    let alt = time::Angle::asin(de.sin() * lat.sin() + de.cos() * lat.cos() * ha.cos());
+    let az = time::Angle::atan2(
+        -de.cos() * ha.sin(),
+        de.sin() * lat.cos() - de.cos() * lat.sin() * ha.cos(),
+    );
+    (az, alt)
+}
+
+/// Equatorial radius of the Earth, in km
+pub const EARTH_RADIUS: f64 = 6378.14;
+
+/// Position and distance (in AU) of an object as seen from the surface of the Earth, rather than its center
+///
+/// The observer is `elevation` meters above the reference ellipsoid.
+pub fn topocentric(
+    c: Coord,
+    dist: f64,
+    d: time::Date,
+    lat: time::Angle,
+    long: time::Angle,
+    elevation: f64,
+) -> (Coord, f64) {
+    // Geocentric latitude, from the flattening of the Earth
+    let u = (0.99664719 * lat.tan()).atan();
+    let h = elevation / (EARTH_RADIUS * 1000.0);
+    let (rho_sin, rho_cos) = (
+        0.99664719 * u.sin() + h * lat.sin(),
+        u.cos() + h * lat.cos(),
+    );
+    let obs = EARTH_RADIUS / 149597870.7;
+    let lst = gmst(d) + long;
+
+    let (ra, de) = c.equatorial();
+    let x = dist * de.cos() * ra.cos() - obs * rho_cos * lst.cos();
+    let y = dist * de.cos() * ra.sin() - obs * rho_cos * lst.sin();
+    let z = dist * de.sin() - obs * rho_sin;
+    (
+        Coord::from_equatorial(time::Angle::atan2(y, x), time::Angle::atan2(z, x.hypot(y))),
+        (x * x + y * y + z * z).sqrt(),
+    )
+}
+
+/// Air pressure in millibars that refraction is usually given for
+pub const STANDARD_PRESSURE: f64 = 1010.0;
+
+/// Air temperature in °C that refraction is usually given for
+pub const STANDARD_TEMPERATURE: f64 = 10.0;
+
+/// Standard refraction at the horizon, 34′
+const HORIZON_REFRACTION: f64 = 34.0 / 60.0;
+
+/// How much refraction there is in air at a pressure (in millibars) and temperature (in °C), relative to
+/// the standard conditions
+fn refraction_scale(pressure: f64, temperature: f64) -> f64 {
+    (pressure / STANDARD_PRESSURE) * ((273.0 + STANDARD_TEMPERATURE) / (273.0 + temperature))
+}
+
+/// Refraction in degrees at a true altitude in degrees, from Sæmundsson's formula (Meeus 16.4)
+///
+/// There is no refraction for objects more than a degree below the horizon, which can't be seen anyway.
+pub fn refraction(altitude: f64, pressure: f64, temperature: f64) -> f64 {
+    if altitude < -1.0 {
+        return 0.0;
+    }
+    let r = 1.02 / (altitude + 10.3 / (altitude + 5.11)).to_radians().tan() / 60.0;
+    r * refraction_scale(pressure, temperature)
+}
+
+/// The number of atmospheres light passes through at an apparent altitude in degrees, from the formula of
+/// Kasten and Young (1989), which holds down to the horizon
+pub fn airmass(altitude: f64) -> Option<f64> {
+    if altitude < 0.0 {
+        return None;
+    }
+    Some(1.0 / (altitude.to_radians().sin() + 0.50572 * (altitude + 6.07995).powf(-1.6364)))
+}
+
+/// Magnitudes of extinction per airmass in the V band at the standard pressure, typical of a good site
+pub const EXTINCTION: f64 = 0.2;
+
+/// How much fainter an object looks at an apparent altitude in degrees, from the air it's seen through
+///
+/// Most of the extinction is from scattering by the air itself, so it's scaled by the pressure.
+pub fn extinction(altitude: f64, pressure: f64) -> Option<f64> {
+    Some(EXTINCTION * pressure / STANDARD_PRESSURE * airmass(altitude)?)
+}
+
+/// The dip of the horizon for an observer above sea level, in degrees
+pub fn dip(elevation: f64) -> f64 {
+    // 1.76′ per square root meter, which includes terrestrial refraction
+    1.76 * elevation.max(0.0).sqrt() / 60.0
+}
+
+/// The altitude of the center of an object, in degrees, when its upper limb is on the horizon
+///
+/// This accounts for refraction (scaled for the weather), the semidiameter of the Sun, Moon, and planets, the Moon's
+/// parallax (positions are geocentric), and the dip of the horizon for observers above sea level.
+pub fn standard_altitude(obj: &CelObj, rf: &RefFrame) -> f64 {
+    let semidiameter = match query::property_of(obj, Property::AngDia, rf) {
+        Ok(Value::Ang(a, _)) => a.degrees() / 2.0,
+        _ => 0.0,
+    };
+    let parallax = match obj {
+        CelObj::Moon => (moon::MOON.pi0 / lunar::distance(rf.date)).degrees(),
+        _ => 0.0,
+    };
+    parallax
+        - semidiameter
+        - HORIZON_REFRACTION * refraction_scale(rf.pressure, rf.temperature)
+        - dip(rf.elevation)
+}
+
+/// The stages of twilight, by how far the Sun's center is below the horizon
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Twilight {
+    Civil,
+    Nautical,
+    Astronomical,
+}
+impl Twilight {
+    /// Depression of the Sun below the horizon, in degrees
+    pub fn depression(self) -> f64 {
+        match self {
+            Twilight::Civil => 6.0,
+            Twilight::Nautical => 12.0,
+            Twilight::Astronomical => 18.0,
+        }
+    }
+}
+
+/// The events in an object's daily motion
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Event {
+    Rise,
+    Set,
+    /// Upper transit, when the object crosses the meridian
+    Transit,
+    /// The start of morning twilight
+    Dawn(Twilight),
+    /// The end of evening twilight
+    Dusk(Twilight),
+}
+
+/// Samples per day when searching for an event, fine enough that the Moon can't rise and set between two
+const SAMPLES: usize = 48;
+
+/// The start of the day, in local mean time at a longitude, that contains a date
+///
+/// A date at 0h UT, as a date given without a time is, stands for that calendar date at the longitude, rather
+/// than the day before it west of Greenwich.
+pub fn day_start(d: time::Date, long: time::Angle) -> time::Date {
+    let offset = long.to_latitude().degrees() / 360.0;
+    let jd = match d.julian() {
+        jd if (jd - 0.5).fract() == 0.0 => jd + 0.5 - offset,
+        jd => jd,
+    };
+    time::Date::from_julian((jd + 0.5 + offset).floor() - 0.5 - offset)
+}
+
+/// Finds the first time in a day that `f` goes from negative to positive (or the reverse if not `rising`)
+///
+/// `f` is sampled, and the crossing is refined by bisection with `f` recomputed (and so the object's
+/// motion accounted for) at each step.
+fn crossing(
+    start: time::Date,
+    rising: bool,
+    f: impl Fn(time::Date) -> Result<f64, &'static str>,
+) -> Result<Result<time::Date, NoEvent>, &'static str> {
+    let at = |i: usize| time::Date::from_julian(start.julian() + (i as f64) / (SAMPLES as f64));
+    let samples = (0..=SAMPLES)
+        .map(|i| f(at(i)))
+        .collect::<Result<Vec<f64>, &'static str>>()?;
+
+    let Some(i) = samples.windows(2).position(|w| {
+        (w[0] < 0.0 && w[1] >= 0.0 && rising) || (w[0] >= 0.0 && w[1] < 0.0 && !rising)
+    }) else {
+        return Ok(Err(if samples.iter().all(|x| *x >= 0.0) {
+            NoEvent::Circumpolar
+        } else if samples.iter().all(|x| *x < 0.0) {
+            NoEvent::NeverRises
+        } else {
+            NoEvent::NotOnDay
+        }));
+    };
+
+    Ok(Ok(refine(at(i), at(i + 1), rising, &f)?))
+}
+
+/// Why there's no dawn or dusk, which is about the Sun's depression rather than the horizon
+fn twilight(r: Result<time::Date, NoEvent>) -> Result<time::Date, NoEvent> {
+    r.map_err(|e| match e {
+        NoEvent::Circumpolar => NoEvent::NoNight,
+        NoEvent::NeverRises => NoEvent::NoTwilight,
+        e => e,
+    })
+}
+
+/// Bisects a sign change of `f` between two dates
+fn refine(
+    lo: time::Date,
+    hi: time::Date,
+    rising: bool,
+    f: &impl Fn(time::Date) -> Result<f64, &'static str>,
+) -> Result<time::Date, &'static str> {
+    let (mut lo, mut hi) = (lo.julian(), hi.julian());
+    // 30 minutes / 2^20 is well under a second
+    for _ in 0..20 {
+        let mid = (lo + hi) / 2.0;
+        if (f(time::Date::from_julian(mid))? < 0.0) == rising {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    Ok(time::Date::from_julian((lo + hi) / 2.0))
+}
+
+/// The time of an event on the observer's day (in local mean time) that contains `rf.date`
+pub fn event(
+    obj: &CelObj,
+    rf: &RefFrame,
+    ev: Event,
+) -> Result<Result<time::Date, NoEvent>, &'static str> {
+    let (lat, long) = rf.latlong.ok_or("Need to specify a lat/long with -l")?;
+    let start = day_start(rf.date, long);
+    let position = |date: time::Date| -> Result<Coord, &'static str> {
+        let Value::Crd(p, _) =
+            query::property_of(obj, Property::Equatorial, &RefFrame { date, ..*rf })?
+        else {
+            unreachable!();
+        };
+        Ok(p)
+    };
+    let altitude = |date: time::Date| -> Result<f64, &'static str> {
+        Ok(altaz(position(date)?, date, lat, long)
+            .1
+            .to_latitude()
+            .degrees())
+    };
+    let above = |date: time::Date| -> Result<f64, &'static str> {
+        Ok(altitude(date)? - standard_altitude(obj, &RefFrame { date, ..*rf }))
+    };
+
+    match ev {
+        Event::Rise => crossing(start, true, above),
+        Event::Set => crossing(start, false, above),
+        Event::Dawn(t) => Ok(twilight(crossing(start, true, |d| {
+            Ok(altitude(d)? + t.depression())
+        })?)),
+        Event::Dusk(t) => Ok(twilight(crossing(start, false, |d| {
+            Ok(altitude(d)? + t.depression())
+        })?)),
+        // The hour angle goes from negative to positive at transit, and wraps around from positive to negative 12h later
+        Event::Transit => Ok(crossing(start, true, |d| {
+            Ok(hour_angle(position(d)?, d, long).to_latitude().degrees())
+        })?
+        .map_err(|_| NoEvent::NotOnDay)),
+    }
+}
+
+/// How long an object is above the horizon, in hours, on the observer's day containing `rf.date`
+///
+/// For the Sun, this is the length of the day.
+pub fn time_above(obj: &CelObj, rf: &RefFrame) -> Result<f64, &'static str> {
+    let (lat, long) = rf.latlong.ok_or("Need to specify a lat/long with -l")?;
+    let start = day_start(rf.date, long);
+    let above = |date: time::Date| -> Result<f64, &'static str> {
+        let frame = RefFrame { date, ..*rf };
+        let Value::Crd(p, _) = query::property_of(obj, Property::Equatorial, &frame)? else {
+            unreachable!();
+        };
+        Ok(altaz(p, date, lat, long).1.to_latitude().degrees() - standard_altitude(obj, &frame))
+    };
+
+    let at = |i: usize| time::Date::from_julian(start.julian() + (i as f64) / (SAMPLES as f64));
+    let mut total = 0.0;
+    let mut prev = above(at(0))?;
+    for i in 0..SAMPLES {
+        let next = above(at(i + 1))?;
+        // Each interval is either wholly above, wholly below, or split by a rise or set
+        total += match (prev >= 0.0, next >= 0.0) {
+            (true, true) => 1.0 / (SAMPLES as f64),
+            (false, false) => 0.0,
+            (false, true) => at(i + 1).julian() - refine(at(i), at(i + 1), true, &above)?.julian(),
+            (true, false) => refine(at(i), at(i + 1), false, &above)?.julian() - at(i).julian(),
+        };
+        prev = next;
+    }
+    Ok(total * 24.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::{Observer, Place};
+
+    fn sun(date: f64, lat: f64, ev: Event) -> Result<time::Date, NoEvent> {
+        let rf = RefFrame {
+            latlong: Some((
+                time::Angle::from_degrees(lat),
+                time::Angle::from_degrees(10.0),
+            )),
+            elevation: 0.0,
+            pressure: 1010.0,
+            temperature: 10.0,
+            place: Place::Mean,
+            observer: Observer::Earth,
+            date: time::Date::from_julian(date),
+        };
+        event(&CelObj::Sun, &rf, ev).unwrap()
+    }
+
+    #[test]
+    fn local_day() {
+        let rf = RefFrame {
+            latlong: Some((
+                time::Angle::from_degrees(40.7128),
+                time::Angle::from_degrees(-74.006),
+            )),
+            elevation: 0.0,
+            pressure: 1010.0,
+            temperature: 10.0,
+            place: Place::Mean,
+            observer: Observer::Earth,
+            // 2026-10-17, with no time
+            date: time::Date::from_julian(2461330.5),
+        };
+        // 11:08 and 22:16 UT, on the 17th in New York
+        let rise = event(&CelObj::Sun, &rf, Event::Rise).unwrap().unwrap();
+        let set = event(&CelObj::Sun, &rf, Event::Set).unwrap().unwrap();
+        assert!(
+            (rise.julian() - 2461330.964).abs() < 0.003,
+            "{}",
+            rise.julian()
+        );
+        assert!(
+            (set.julian() - 2461331.428).abs() < 0.003,
+            "{}",
+            set.julian()
+        );
+        // Later in the day, it's still the same day
+        let evening = RefFrame {
+            date: time::Date::from_julian(2461331.4),
+            ..rf
+        };
+        assert_eq!(
+            event(&CelObj::Sun, &evening, Event::Rise).unwrap(),
+            Ok(rise)
+        );
+    }
+
+    #[test]
+    fn twilight_reasons() {
+        // The solstices of 2026
+        let (june, december) = (2461212.5, 2461395.5);
+        assert_eq!(
+            sun(june, 60.0, Event::Dawn(Twilight::Astronomical)),
+            Err(NoEvent::NoNight)
+        );
+        assert_eq!(
+            sun(december, 85.0, Event::Dusk(Twilight::Civil)),
+            Err(NoEvent::NoTwilight)
+        );
+        assert_eq!(sun(december, 85.0, Event::Rise), Err(NoEvent::NeverRises));
+        assert!(sun(june, 60.0, Event::Dawn(Twilight::Civil)).is_ok());
+    }
+}
@@ -0,0 +1,431 @@
+//! JSON output
+//!
+//! Every [`Value`] is an object with a `type` field, and fields named with their units:
+//!
+//! | `type`       | Fields                                                                   |
+//! |--------------|--------------------------------------------------------------------------|
+//! | `date`       | `unix`, `jd`, `iso` (UTC)                                                |
+//! | `angle`      | `degrees` (signed for latitudes), `hours` (for times only)               |
+//! | `equatorial` | `ra_hours`, `dec_degrees`                                                |
+//! | `ecliptic`   | `longitude_degrees`, `latitude_degrees`, `epoch_jd`                      |
+//! | `horizontal` | `altitude_degrees`, `azimuth_degrees`                                    |
+//! | `galactic`   | `longitude_degrees`, `latitude_degrees`, also for `supergalactic`        |
+//! | `offset`     | `east_arcsec`, `north_arcsec`                                            |
+//! | `number`     | `value`                                                                  |
+//! | `duration`   | `hours`                                                                  |
+//! | `text`       | `value`                                                                  |
+//! | `distance`   | `au`, `km`, `ly`                                                         |
+//! | `phase`      | `angle_degrees`, `illuminated_fraction`, `name`, `emoji`                 |
+//!
+//! Rise, set, transit, dawn, and dusk times are `date` objects, or `null` when they don't happen that day.
+
+use crate::galactic;
+use crate::horizon::{self, Twilight};
+use crate::moons;
+use crate::orbit;
+use crate::query::{self, Property};
+use crate::table::Table;
+use crate::value::{AngView, CelObj, CrdView, PhaseView, Place, RefFrame, Value};
+use pracstro::time;
+use std::fmt;
+
+/// A JSON document
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Num(f64),
+    Str(String),
+    Arr(Vec<Json>),
+    Obj(Vec<(&'static str, Json)>),
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            // JSON has no representation of NaN or infinity
+            Json::Num(n) if !n.is_finite() => write!(f, "null"),
+            Json::Num(n) => write!(f, "{}", n),
+            Json::Str(s) => {
+                write!(f, "\"")?;
+                for c in s.chars() {
+                    match c {
+                        '"' => write!(f, "\\\"")?,
+                        '\\' => write!(f, "\\\\")?,
+                        '\n' => write!(f, "\\n")?,
+                        '\r' => write!(f, "\\r")?,
+                        '\t' => write!(f, "\\t")?,
+                        c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+                        c => write!(f, "{}", c)?,
+                    }
+                }
+                write!(f, "\"")
+            }
+            Json::Arr(a) => {
+                write!(f, "[")?;
+                for (i, x) in a.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", x)?;
+                }
+                write!(f, "]")
+            }
+            Json::Obj(o) => {
+                write!(f, "{{")?;
+                for (i, (k, x)) in o.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}:{}", Json::Str(k.to_string()), x)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// The name used for a property in JSON
+pub fn property_key(p: &Property) -> &'static str {
+    match p {
+        Property::Equatorial => "equatorial",
+        Property::Ecliptic => "ecliptic",
+        Property::Galactic => "galactic",
+        Property::Supergalactic => "supergalactic",
+        Property::Horizontal => "horizontal",
+        Property::TopoEquatorial => "topocentric_equatorial",
+        Property::Distance => "distance",
+        Property::TopoDistance => "topocentric_distance",
+        Property::Magnitude => "magnitude",
+        Property::PhaseDefault => "phase",
+        Property::PhaseName => "phase_name",
+        Property::PhaseEmoji => "phase_emoji",
+        Property::PhaseAngle => "phase_angle",
+        Property::AngDia => "angular_diameter",
+        Property::TopoAngDia => "topocentric_angular_diameter",
+        Property::ApparentAltitude => "apparent_altitude",
+        Property::Airmass => "airmass",
+        Property::ExtMagnitude => "extincted_magnitude",
+        Property::IllumFrac => "illuminated_fraction",
+        Property::Rise => "rise",
+        Property::Set => "set",
+        Property::Transit => "transit",
+        Property::Dawn(Twilight::Civil) => "civil_dawn",
+        Property::Dusk(Twilight::Civil) => "civil_dusk",
+        Property::Dawn(Twilight::Nautical) => "nautical_dawn",
+        Property::Dusk(Twilight::Nautical) => "nautical_dusk",
+        Property::Dawn(Twilight::Astronomical) => "astronomical_dawn",
+        Property::Dusk(Twilight::Astronomical) => "astronomical_dusk",
+        Property::DayLength => "day_length",
+        Property::Illumination => "illumination",
+        Property::Offset => "offset",
+        Property::AngBet(_) => "angle_between",
+    }
+}
+
+pub fn date(d: time::Date) -> Json {
+    use chrono::prelude::*;
+    Json::Obj(vec![
+        ("type", Json::Str("date".into())),
+        ("unix", Json::Num(d.unix())),
+        ("jd", Json::Num(d.julian())),
+        (
+            "iso",
+            match DateTime::from_timestamp(d.unix().floor() as i64, 0) {
+                Some(t) => Json::Str(t.format("%Y-%m-%dT%H:%M:%SZ").to_string()),
+                None => Json::Null,
+            },
+        ),
+    ])
+}
+
+pub fn value(v: &Value) -> Json {
+    fn obj(t: &'static str, mut fields: Vec<(&'static str, Json)>) -> Json {
+        fields.insert(0, ("type", Json::Str(t.into())));
+        Json::Obj(fields)
+    }
+    match v {
+        Value::Date(d) | Value::RsTime(Ok(d)) => date(*d),
+        Value::RsTime(Err(e)) => obj("none", vec![("reason", Json::Str(e.name().into()))]),
+        Value::Ang(a, AngView::Angle) => obj("angle", vec![("degrees", Json::Num(a.degrees()))]),
+        Value::Ang(a, AngView::Latitude) => obj(
+            "angle",
+            vec![("degrees", Json::Num(a.to_latitude().degrees()))],
+        ),
+        Value::Ang(a, AngView::Time) => obj(
+            "angle",
+            vec![
+                ("degrees", Json::Num(a.degrees())),
+                ("hours", Json::Num(a.decimal())),
+            ],
+        ),
+        Value::Crd(c, CrdView::Equatorial) => {
+            let (ra, de) = c.equatorial();
+            obj(
+                "equatorial",
+                vec![
+                    ("ra_hours", Json::Num(ra.decimal())),
+                    ("dec_degrees", Json::Num(de.to_latitude().degrees())),
+                ],
+            )
+        }
+        Value::Crd(c, CrdView::Ecliptic(d)) => {
+            let (lambda, beta) = c.ecliptic(*d);
+            obj(
+                "ecliptic",
+                vec![
+                    ("longitude_degrees", Json::Num(lambda.degrees())),
+                    ("latitude_degrees", Json::Num(beta.to_latitude().degrees())),
+                    ("epoch_jd", Json::Num(d.julian())),
+                ],
+            )
+        }
+        Value::Crd(c, CrdView::Galactic(d)) => {
+            let (l, b) = galactic::galactic(*c, *d);
+            obj(
+                "galactic",
+                vec![
+                    ("longitude_degrees", Json::Num(l.degrees())),
+                    ("latitude_degrees", Json::Num(b.to_latitude().degrees())),
+                ],
+            )
+        }
+        Value::Crd(c, CrdView::Supergalactic(d)) => {
+            let (l, b) = galactic::supergalactic(*c, *d);
+            obj(
+                "supergalactic",
+                vec![
+                    ("longitude_degrees", Json::Num(l.degrees())),
+                    ("latitude_degrees", Json::Num(b.to_latitude().degrees())),
+                ],
+            )
+        }
+        Value::Crd(c, CrdView::Offset(from)) => {
+            let (east, north) = moons::offset(*c, *from);
+            obj(
+                "offset",
+                vec![
+                    ("east_arcsec", Json::Num(east)),
+                    ("north_arcsec", Json::Num(north)),
+                ],
+            )
+        }
+        Value::Crd(c, CrdView::Horizontal(d, lat, long)) => {
+            let (az, alt) = horizon::altaz(*c, *d, *lat, *long);
+            obj(
+                "horizontal",
+                vec![
+                    ("altitude_degrees", Json::Num(alt.to_latitude().degrees())),
+                    ("azimuth_degrees", Json::Num(az.degrees())),
+                ],
+            )
+        }
+        Value::Duration(h) => obj("duration", vec![("hours", Json::Num(*h))]),
+        Value::Num(n) => obj("number", vec![("value", Json::Num(*n))]),
+        Value::Int(n) => obj("number", vec![("value", Json::Num(*n as f64))]),
+        Value::Str(s) => obj("text", vec![("value", Json::Str(s.clone()))]),
+        Value::Unavailable(reason) => obj("none", vec![("reason", Json::Str((*reason).into()))]),
+        Value::Dist(d) => obj(
+            "distance",
+            vec![
+                ("au", Json::Num(*d)),
+                ("km", Json::Num(d * 149597870.7)),
+                ("ly", Json::Num(d / 63241.07708)),
+            ],
+        ),
+        Value::Phase(pa, _) => obj(
+            "phase",
+            vec![
+                ("angle_degrees", Json::Num(pa.degrees())),
+                ("illuminated_fraction", Json::Num((1.0 - pa.cos()) / 2.0)),
+                (
+                    "name",
+                    Json::Str(Value::Phase(*pa, PhaseView::PhaseName).to_string()),
+                ),
+                (
+                    "emoji",
+                    Json::Str(Value::Phase(*pa, PhaseView::Emoji(false)).to_string()),
+                ),
+            ],
+        ),
+    }
+}
+
+/// The name and kind of an object
+pub fn object(name: &str, obj: &CelObj) -> Json {
+    Json::Obj(vec![
+        ("name", Json::Str(name.into())),
+        (
+            "kind",
+            Json::Str(
+                match obj {
+                    CelObj::Planet(_) => "planet",
+                    CelObj::Moon => "moon",
+                    CelObj::Sun => "sun",
+                    CelObj::Star(_) => "star",
+                    CelObj::Minor(o) => match o.magnitude {
+                        orbit::Magnitude::Asteroid { .. } => "asteroid",
+                        orbit::Magnitude::Comet { .. } => "comet",
+                    },
+                    CelObj::Satellite(_) => "satellite",
+                    CelObj::PlanetMoon(_) => "planetary_moon",
+                    CelObj::Crd(..) => "coordinate",
+                }
+                .into(),
+            ),
+        ),
+    ])
+}
+
+pub fn place(p: Place) -> Json {
+    Json::Str(
+        match p {
+            Place::Mean => "mean",
+            Place::Apparent => "apparent",
+        }
+        .into(),
+    )
+}
+
+/// The observer, and the weather where they are
+pub fn location(rf: &RefFrame) -> Json {
+    match rf.latlong {
+        Some((lat, long)) => Json::Obj(vec![
+            ("latitude_degrees", Json::Num(lat.to_latitude().degrees())),
+            ("longitude_degrees", Json::Num(long.to_latitude().degrees())),
+            ("elevation_meters", Json::Num(rf.elevation)),
+            ("pressure_mbar", Json::Num(rf.pressure)),
+            ("temperature_celsius", Json::Num(rf.temperature)),
+        ]),
+        None => Json::Null,
+    }
+}
+
+/// The properties in a JSON report, properties that don't apply to an object are `null`
+pub const REPORT_PROPERTIES: [Property; 15] = [
+    Property::Equatorial,
+    Property::Ecliptic,
+    Property::Galactic,
+    Property::Horizontal,
+    Property::TopoEquatorial,
+    Property::Distance,
+    Property::TopoDistance,
+    Property::Magnitude,
+    Property::AngDia,
+    Property::TopoAngDia,
+    Property::PhaseDefault,
+    Property::Rise,
+    Property::Transit,
+    Property::Set,
+    Property::Offset,
+];
+
+pub fn report(name: &str, obj: &CelObj, rf: &RefFrame) -> Json {
+    let mut doc = vec![
+        ("object", object(name, obj)),
+        ("date", date(rf.date)),
+        ("location", location(rf)),
+        ("place", place(rf.place)),
+        ("observer", Json::Str(rf.observer.to_string())),
+    ];
+    doc.push((
+        "properties",
+        Json::Obj(
+            REPORT_PROPERTIES
+                .iter()
+                .map(|p| {
+                    (
+                        property_key(p),
+                        query::property_of(obj, p.clone(), rf).map_or(Json::Null, |v| value(&v)),
+                    )
+                })
+                .collect(),
+        ),
+    ));
+    Json::Obj(doc)
+}
+
+pub fn query(
+    name: &str,
+    obj: &CelObj,
+    rf: &RefFrame,
+    props: &[Property],
+    values: &[Value],
+) -> Json {
+    let mut doc = vec![
+        ("object", object(name, obj)),
+        ("date", date(rf.date)),
+        ("location", location(rf)),
+        ("place", place(rf.place)),
+        ("observer", Json::Str(rf.observer.to_string())),
+    ];
+    doc.push((
+        "properties",
+        Json::Arr(
+            props
+                .iter()
+                .zip(values)
+                .map(|(p, v)| {
+                    Json::Obj(vec![
+                        ("property", Json::Str(property_key(p).into())),
+                        ("name", Json::Str(p.to_string())),
+                        ("value", value(v)),
+                    ])
+                })
+                .collect(),
+        ),
+    ));
+    Json::Obj(doc)
+}
+
+pub fn table(t: &Table) -> Json {
+    Json::Obj(vec![
+        (
+            "columns",
+            Json::Arr(t.columns.iter().map(|c| Json::Str(c.clone())).collect()),
+        ),
+        (
+            "rows",
+            Json::Arr(
+                t.rows
+                    .iter()
+                    .map(|r| Json::Arr(r.iter().map(value).collect()))
+                    .collect(),
+            ),
+        ),
+    ])
+}
+
+pub fn ephemeris(name: &str, obj: &CelObj, rf: &RefFrame, t: &Table) -> Json {
+    let mut doc = vec![
+        ("object", object(name, obj)),
+        ("location", location(rf)),
+        ("place", place(rf.place)),
+        ("observer", Json::Str(rf.observer.to_string())),
+    ];
+    if let Json::Obj(fields) = table(t) {
+        doc.extend(fields);
+    }
+    Json::Obj(doc)
+}
+
+/// The events found in a range of dates
+///
+/// Searches about particular objects list them first.
+pub fn events(objects: Vec<Json>, start: time::Date, end: time::Date, t: &Table) -> Json {
+    let mut doc = Vec::new();
+    if !objects.is_empty() {
+        doc.push(("objects", Json::Arr(objects)));
+    }
+    doc.extend([("start", date(start)), ("end", date(end))]);
+    if let Json::Obj(fields) = table(t) {
+        doc.extend(fields);
+    }
+    Json::Obj(doc)
+}
+
+pub fn error(msg: &str) -> Json {
+    Json::Obj(vec![("error", Json::Str(msg.into()))])
+}
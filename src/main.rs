@@ -1,12 +1,28 @@
-use pracstro::time;
 use std::env;
 
-use crate::text::{ANSI_DRIVER, HTML_DRIVER, TEXT_DRIVER};
-
 /// Handles the reading and querying of the catalog of celestial objects
+pub mod apparent;
 pub mod catalog;
+pub mod cgi;
+pub mod cli;
+pub mod conjunction;
+pub mod eclipse;
+pub mod events;
+pub mod galactic;
+pub mod heliocentric;
+pub mod horizon;
+pub mod json;
+pub mod lunar;
+pub mod moons;
+pub mod mpc;
+pub mod occultation;
+pub mod orbit;
 pub mod parse;
 pub mod query;
+pub mod request;
+pub mod satellite;
+pub mod sgp4;
+pub mod table;
 pub mod text;
 pub mod tile;
 pub mod value;
@@ -76,85 +92,11 @@ pub mod timestep {
 }
 
 fn main() {
-    let argv: Vec<String> = env::args().collect();
-    let driver = match argv[1].as_str() {
-        "html" => HTML_DRIVER,
-        "ansi" => ANSI_DRIVER,
-        _ => TEXT_DRIVER,
-    };
-
-    let date = time::Date::now();
-    let obj = parse::object(argv[2].as_str(), &catalog::read());
-
-    if obj.is_err() {
-        println!("The specified object does not exist");
+    // Web servers set GATEWAY_INTERFACE for every CGI script they run
+    if env::var_os("GATEWAY_INTERFACE").is_some() {
+        cgi::main();
         return;
     }
-    let obj = obj.unwrap();
-
-    let data = query::generate_cgi_data(&obj, date);
-
-    print!("{}", driver.header);
-    print!(
-        "Report for {} on JD{:0.2}{}",
-        argv[2],
-        date.julian(),
-        driver.eol
-    );
-
-    let location_tile: Vec<String> = (0..=14)
-        .map(|x| {
-            tile::location::location_tile(data.location, x, date)
-                .into_iter()
-                .map(driver.render_atom)
-                .collect::<String>()
-        })
-        .collect();
-
-    let phase_tile: Vec<String> = if let Some(phaseangle) = data.phaseangle {
-        (0..=14)
-            .map(|x| {
-                tile::phase::phase_tile(phaseangle, x, &obj)
-                    .into_iter()
-                    .map(driver.render_atom)
-                    .collect::<String>()
-            })
-            .collect()
-    } else {
-        (0..=14)
-            .map(|x| {
-                tile::na_nostart_tile(x, " Phase ".into())
-                    .into_iter()
-                    .map(driver.render_atom)
-                    .collect::<String>()
-            })
-            .collect()
-    };
-
-    let brightness_tile: Vec<String> = (0..=14)
-        .map(|x| {
-            tile::brightness::brightness_tile(data.brightness, x)
-                .into_iter()
-                .map(driver.render_atom)
-                .collect::<String>()
-        })
-        .collect();
-
-    let distance_tile: Vec<String> = (0..=14)
-        .map(|x| {
-            tile::distance::distance_tile(data.dist, data.angdia, x)
-                .into_iter()
-                .map(driver.render_atom)
-                .collect::<String>()
-        })
-        .collect();
-
-    for i in 0..=13 {
-        print!("{}{}{}", location_tile[i], phase_tile[i], driver.eol);
-    }
-    for i in 0..=14 {
-        print!("{}{}{}", distance_tile[i], brightness_tile[i], driver.eol);
-    }
 
-    print!("{}", driver.footer);
+    cli::main(&env::args().skip(1).collect::<Vec<String>>());
 }
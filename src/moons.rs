@@ -0,0 +1,478 @@
+//! The major moons of Jupiter and Saturn, and the phenomena of Jupiter's Galilean moons
+//!
+//! The Galilean moons follow the low accuracy theory of Meeus (Astronomical Algorithms, ch. 44), good to about
+//! a tenth of Jupiter's radius, or a few minutes in the times of their transits, occultations, and eclipses.
+//! Saturn's moons are in circular orbits in their Laplace planes, with the mean longitudes (and for Mimas and
+//! Tethys, the libration of their resonance) of Meeus ch. 46 and the epochs and planes of JPL's mean elements.
+//! Only Titan's orbit is eccentric enough to matter, the others are within an arcsecond or so.
+
+use crate::apparent::LIGHT_TIME;
+use crate::events;
+use crate::table::Table;
+use crate::value::Value;
+use pracstro::{coord::Coord, sol, time};
+use std::fmt;
+
+/// Kilometers in an AU
+const AU: f64 = 149597870.7;
+
+/// Equatorial radius of Jupiter, in km
+pub const JUPITER_RADIUS: f64 = 71492.0;
+
+/// Ratio of Jupiter's polar radius to its equatorial radius
+const JUPITER_FLATTENING: f64 = 0.93513;
+
+/// North pole of Jupiter's equator, as J2000 right ascension and declination in degrees
+const JUPITER_POLE: (f64, f64) = (268.057, 64.495);
+
+/// North pole of Saturn's equator, which is the Laplace plane of the inner moons
+const SATURN_POLE: (f64, f64) = (40.589, 83.537);
+
+/// North pole of Titan's Laplace plane, tilted from Saturn's equator by the pull of the Sun
+const TITAN_POLE: (f64, f64) = (36.41, 83.94);
+
+/// Interval between samples when searching for the phenomena of the Galilean moons, in days
+///
+/// The shortest of them are grazing transits and eclipses of Callisto, anything much shorter than this is missed.
+const STEP: f64 = 5.0 / 1440.0;
+
+/// The longest range phenomena can be searched over, in days, there are about eight a day
+const MAX_RANGE: f64 = 366.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Moon {
+    Io,
+    Europa,
+    Ganymede,
+    Callisto,
+    Mimas,
+    Enceladus,
+    Tethys,
+    Dione,
+    Rhea,
+    Titan,
+}
+impl Moon {
+    /// The moons of Jupiter, in order from it
+    pub const GALILEAN: [Moon; 4] = [Moon::Io, Moon::Europa, Moon::Ganymede, Moon::Callisto];
+
+    /// The planet the moon goes around
+    pub fn planet(self) -> sol::Planet {
+        match self {
+            Moon::Io | Moon::Europa | Moon::Ganymede | Moon::Callisto => sol::JUPITER,
+            _ => sol::SATURN,
+        }
+    }
+
+    /// Sidereal period, in days
+    pub fn period(self) -> f64 {
+        match self {
+            Moon::Io => 1.769138,
+            Moon::Europa => 3.551181,
+            Moon::Ganymede => 7.154553,
+            Moon::Callisto => 16.689017,
+            Moon::Mimas => 0.942422,
+            Moon::Enceladus => 1.370218,
+            Moon::Tethys => 1.887802,
+            Moon::Dione => 2.736915,
+            Moon::Rhea => 4.517500,
+            Moon::Titan => 15.945421,
+        }
+    }
+
+    /// Mean radius, in km
+    fn radius(self) -> f64 {
+        match self {
+            Moon::Io => 1821.6,
+            Moon::Europa => 1560.8,
+            Moon::Ganymede => 2631.2,
+            Moon::Callisto => 2410.3,
+            Moon::Mimas => 198.2,
+            Moon::Enceladus => 252.1,
+            Moon::Tethys => 531.1,
+            Moon::Dione => 561.4,
+            Moon::Rhea => 763.8,
+            Moon::Titan => 2574.7,
+        }
+    }
+
+    /// Magnitude at 1 AU from the Sun and the Earth, at full phase
+    fn v0(self) -> f64 {
+        match self {
+            Moon::Io => -1.68,
+            Moon::Europa => -1.41,
+            Moon::Ganymede => -2.09,
+            Moon::Callisto => -1.05,
+            Moon::Mimas => 3.3,
+            Moon::Enceladus => 2.1,
+            Moon::Tethys => 0.6,
+            Moon::Dione => 0.8,
+            Moon::Rhea => 0.1,
+            Moon::Titan => -1.2,
+        }
+    }
+}
+impl fmt::Display for Moon {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Position of the Galilean moons in Jupiter's equatorial radii, as (west, north, toward the observer) (Meeus 44)
+///
+/// West and north are along Jupiter's equator and axis. The observer is the Earth, or the Sun for where the moons'
+/// shadows fall, both at the time the light reaching the Earth left Jupiter.
+pub fn galilean(d: time::Date, from_sun: bool) -> [(f64, f64, f64); 4] {
+    let sin = |x: f64| x.to_radians().sin();
+    let cos = |x: f64| x.to_radians().cos();
+    let d = d.julian() - 2451545.0;
+
+    let v = 172.74 + 0.00111588 * d;
+    let m = 357.529 + 0.9856003 * d;
+    let n = 20.020 + 0.0830853 * d + 0.329 * sin(v);
+    let j = 66.115 + 0.9025179 * d - 0.329 * sin(v);
+    // The equations of center of the Earth and Jupiter
+    let a = 1.915 * sin(m) + 0.020 * sin(2.0 * m);
+    let b = 5.555 * sin(n) + 0.168 * sin(2.0 * n);
+    let k = j + a - b;
+    // Distances of the Earth and Jupiter from the Sun, and of Jupiter from the Earth, in AU
+    let re = 1.00014 - 0.01671 * cos(m) - 0.00014 * cos(2.0 * m);
+    let rj = 5.20872 - 0.25208 * cos(n) - 0.00611 * cos(2.0 * n);
+    let delta = (rj * rj + re * re - 2.0 * rj * re * cos(k)).sqrt();
+    // The angle between the Sun and the Earth seen from Jupiter
+    let psi = (re / delta * sin(k)).asin().to_degrees();
+    // Jupiter's heliocentric longitude, and the declinations of the Sun and the Earth above its equator
+    let lambda = 34.35 + 0.083091 * d + 0.329 * sin(v) + b;
+    let ds = 3.12 * sin(lambda + 42.8);
+    let de = ds
+        - 2.22 * sin(psi) * cos(lambda + 22.0)
+        - 1.30 * (rj - delta) / delta * sin(lambda - 100.5);
+    let (psi, dec) = if from_sun { (0.0, ds) } else { (psi, de) };
+
+    let t = d - delta * LIGHT_TIME;
+    let u = [
+        163.8069 + 203.4058646 * t,
+        358.4140 + 101.2916335 * t,
+        5.7176 + 50.2345180 * t,
+        224.8092 + 21.4879800 * t,
+    ]
+    .map(|u| u + psi - b);
+    let g = 331.18 + 50.310482 * t;
+    let h = 87.45 + 21.569231 * t;
+    // The largest perturbations, from the resonance of the inner three and from the Sun
+    let r = [
+        5.9057 - 0.0244 * cos(2.0 * (u[0] - u[1])),
+        9.3966 - 0.0882 * cos(2.0 * (u[1] - u[2])),
+        14.9883 - 0.0216 * cos(g),
+        26.3627 - 0.1939 * cos(h),
+    ];
+    let u = [
+        u[0] + 0.473 * sin(2.0 * (u[0] - u[1])),
+        u[1] + 1.065 * sin(2.0 * (u[1] - u[2])),
+        u[2] + 0.165 * sin(g),
+        u[3] + 0.843 * sin(h),
+    ];
+    [0, 1, 2, 3].map(|i| {
+        (
+            r[i] * sin(u[i]),
+            -r[i] * cos(u[i]) * sin(dec),
+            r[i] * cos(u[i]),
+        )
+    })
+}
+
+/// Unit vector toward a right ascension and declination
+fn unit(ra: f64, de: f64) -> [f64; 3] {
+    let (ra, de) = (ra.to_radians(), de.to_radians());
+    [de.cos() * ra.cos(), de.cos() * ra.sin(), de.sin()]
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// Position of one of Saturn's moons relative to Saturn, in km, referred to the J2000 equator
+///
+/// `t` is days from J2000.
+fn saturnian(moon: Moon, t: f64) -> [f64; 3] {
+    // Meeus's libration of Mimas and Tethys in their resonance, which has a period of 70 years
+    let w = (5.095 * ((t + 2451545.0 - 2433282.423) / 365.25 + 83.61)).to_radians();
+    // Semimajor axis in km, mean longitude at J2000 and its rate in degrees a day, along the plane from its
+    // ascending node on the J2000 equator
+    let (a, lon, pole) = match moon {
+        Moon::Mimas => (
+            185539.0,
+            160.374 + 381.994497 * t
+                - 43.57 * w.sin()
+                - 0.720 * (3.0 * w).sin()
+                - 0.02144 * (5.0 * w).sin(),
+            SATURN_POLE,
+        ),
+        Moon::Enceladus => (238042.0, 182.269 + 262.7319002 * t, SATURN_POLE),
+        Moon::Tethys => (
+            294672.0,
+            188.411
+                + 190.69791226 * t
+                + 2.063 * w.sin()
+                + 0.03409 * (3.0 * w).sin()
+                + 0.001015 * (5.0 * w).sin(),
+            SATURN_POLE,
+        ),
+        Moon::Dione => (377415.0, 176.962 + 131.53493193 * t, SATURN_POLE),
+        Moon::Rhea => (527068.0, 52.442 + 79.69004720 * t, SATURN_POLE),
+        Moon::Titan => (1221870.0, 11.902 + 22.57697855 * t, TITAN_POLE),
+        _ => unreachable!(),
+    };
+    // Titan's eccentricity is 0.0288, and its periapsis goes around in 703 years
+    let (r, lon) = if moon == Moon::Titan {
+        let e = 0.0288;
+        let m = (lon - (208.592 + 0.001402 * t)).to_radians();
+        (
+            a * (1.0 - e * m.cos()),
+            lon + (2.0 * e * m.sin() + 1.25 * e * e * (2.0 * m).sin()).to_degrees(),
+        )
+    } else {
+        (a, lon)
+    };
+
+    // The ascending node of the plane on the equator, and the direction 90° along the plane from it
+    let p = unit(pole.0, pole.1);
+    let n = unit(pole.0 + 90.0, 0.0);
+    let m = [
+        p[1] * n[2] - p[2] * n[1],
+        p[2] * n[0] - p[0] * n[2],
+        p[0] * n[1] - p[1] * n[0],
+    ];
+    let (s, c) = lon.to_radians().sin_cos();
+    [0, 1, 2].map(|i| r * (c * n[i] + s * m[i]))
+}
+
+/// Position angle of a planet's north pole, from north through east, in radians
+fn position_angle(pole: (f64, f64), ra: f64, de: f64) -> f64 {
+    let (a0, d0) = (pole.0.to_radians(), pole.1.to_radians());
+    let (ra, de) = (ra.to_radians(), de.to_radians());
+    (d0.cos() * (a0 - ra).sin()).atan2(d0.sin() * de.cos() - d0.cos() * de.sin() * (a0 - ra).cos())
+}
+
+/// Offset of a moon from its planet seen from the Earth, in km, as (east, north, toward the Earth)
+fn offset_km(moon: Moon, d: time::Date) -> (f64, f64, f64) {
+    let planet = moon.planet();
+    let (ra, de) = planet.location(d).equatorial();
+    let (ra, de) = (ra.degrees(), de.to_latitude().degrees());
+    match moon {
+        Moon::Io | Moon::Europa | Moon::Ganymede | Moon::Callisto => {
+            let i = Moon::GALILEAN.iter().position(|&m| m == moon).unwrap();
+            let (x, y, z) = galilean(d, false)[i];
+            let (s, c) = position_angle(JUPITER_POLE, ra, de).sin_cos();
+            (
+                (-x * c + y * s) * JUPITER_RADIUS,
+                (x * s + y * c) * JUPITER_RADIUS,
+                z * JUPITER_RADIUS,
+            )
+        }
+        _ => {
+            // Seen as it was when the light left it
+            let t = d.julian() - LIGHT_TIME * planet.distance(d) - 2451545.0;
+            let v = saturnian(moon, t);
+            let (ra, de) = (ra.to_radians(), de.to_radians());
+            (
+                dot(v, [-ra.sin(), ra.cos(), 0.0]),
+                dot(v, [-de.sin() * ra.cos(), -de.sin() * ra.sin(), de.cos()]),
+                -dot(v, unit(ra.to_degrees(), de.to_degrees())),
+            )
+        }
+    }
+}
+
+/// Position of a moon, given the position of its planet seen from the Earth
+///
+/// The planet's position can be mean or apparent, the offset is the same for both.
+pub fn shift(planet: Coord, moon: Moon, d: time::Date) -> Coord {
+    let (east, north, _) = offset_km(moon, d);
+    let dist = moon.planet().distance(d) * AU;
+    let (ra, de) = planet.equatorial();
+    let de = de.to_latitude().radians();
+    Coord::from_equatorial(
+        time::Angle::from_degrees((ra.radians() + east / dist / de.cos()).to_degrees()),
+        time::Angle::from_degrees((de + north / dist).to_degrees()),
+    )
+}
+
+/// Position of a moon relative to the Sun, in AU along the axes of the J2000 equator
+///
+/// Saturn's moons are where they are at the date, the Galilean moons are put back in space from where they're
+/// seen from the Earth.
+pub fn heliocentric(moon: Moon, d: time::Date) -> (f64, f64, f64) {
+    let planet = moon.planet();
+    let v = match moon {
+        Moon::Io | Moon::Europa | Moon::Ganymede | Moon::Callisto => {
+            let (east, north, toward) = offset_km(moon, d);
+            let (ra, de) = planet.location(d).equatorial();
+            let (ra, de) = (ra.degrees(), de.to_latitude().degrees());
+            let u = unit(ra, de);
+            let (ra, de) = (ra.to_radians(), de.to_radians());
+            let e = [-ra.sin(), ra.cos(), 0.0];
+            let n = [-de.sin() * ra.cos(), -de.sin() * ra.sin(), de.cos()];
+            [0, 1, 2].map(|i| east * e[i] + north * n[i] - toward * u[i])
+        }
+        _ => saturnian(moon, d.julian() - 2451545.0),
+    };
+    let (x, y, z) = planet.locationcart(d);
+    (x + v[0] / AU, y + v[1] / AU, z + v[2] / AU)
+}
+
+/// Distance of a moon from the Earth, in AU
+pub fn distance(moon: Moon, d: time::Date) -> f64 {
+    moon.planet().distance(d) - offset_km(moon, d).2 / AU
+}
+
+/// Angular diameter of a moon
+pub fn angdia(moon: Moon, d: time::Date) -> time::Angle {
+    time::Angle::from_degrees((2.0 * moon.radius() / (distance(moon, d) * AU)).to_degrees())
+}
+
+/// Magnitude of a moon, without any change with phase, which is small seen from the Earth
+pub fn magnitude(moon: Moon, d: time::Date) -> f64 {
+    let (x, y, z) = moon.planet().locationcart(d);
+    moon.v0() + 5.0 * (distance(moon, d) * (x * x + y * y + z * z).sqrt()).log10()
+}
+
+/// Offset of one position from another, as arcseconds east and north
+pub fn offset(c: Coord, from: Coord) -> (f64, f64) {
+    let (ra, de) = c.equatorial();
+    let (ra0, de0) = from.equatorial();
+    let dra = (ra.degrees() - ra0.degrees() + 180.0).rem_euclid(360.0) - 180.0;
+    (
+        dra * de0.to_latitude().radians().cos() * 3600.0,
+        (de.to_latitude().degrees() - de0.to_latitude().degrees()) * 3600.0,
+    )
+}
+
+/// The phenomena of the Galilean moons, with Jupiter or its shadow
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Phenomenon {
+    /// The moon crossing in front of Jupiter
+    Transit,
+    /// The moon's shadow crossing Jupiter
+    ShadowTransit,
+    /// The moon behind Jupiter
+    Occultation,
+    /// The moon in Jupiter's shadow
+    Eclipse,
+}
+impl Phenomenon {
+    const ALL: [Phenomenon; 4] = [
+        Phenomenon::Transit,
+        Phenomenon::ShadowTransit,
+        Phenomenon::Occultation,
+        Phenomenon::Eclipse,
+    ];
+
+    /// How far inside Jupiter's disk, or its shadow, a moon is, in Jupiter's radii squared
+    ///
+    /// This is positive during the phenomenon and negative otherwise. Jupiter's shadow is taken as a cylinder,
+    /// which narrows by a few percent out at Callisto.
+    fn depth(self, i: usize, d: time::Date) -> f64 {
+        let sun = matches!(self, Phenomenon::ShadowTransit | Phenomenon::Eclipse);
+        let front = matches!(self, Phenomenon::Transit | Phenomenon::ShadowTransit);
+        let (x, y, z) = galilean(d, sun)[i];
+        if (z > 0.0) != front {
+            return -1.0;
+        }
+        1.0 - x * x - (y / JUPITER_FLATTENING).powi(2)
+    }
+}
+impl fmt::Display for Phenomenon {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Phenomenon::Transit => "Transit",
+                Phenomenon::ShadowTransit => "Shadow Transit",
+                Phenomenon::Occultation => "Occultation",
+                Phenomenon::Eclipse => "Eclipse",
+            }
+        )
+    }
+}
+
+/// The phenomena of the Galilean moons under way at a date
+pub fn in_progress(d: time::Date) -> Vec<(Moon, Phenomenon)> {
+    let mut found = Vec::new();
+    for (i, &moon) in Moon::GALILEAN.iter().enumerate() {
+        for ph in Phenomenon::ALL {
+            if ph.depth(i, d) > 0.0 {
+                found.push((moon, ph));
+            }
+        }
+    }
+    found
+}
+
+/// The starts and ends of the phenomena of the Galilean moons in a range, in order
+///
+/// These are when the center of the moon, or of its shadow, crosses the edge of Jupiter's disk or shadow, seen
+/// from the center of the Earth. They're all listed, whether or not Jupiter is up, or the Sun is.
+pub fn phenomena(
+    start: time::Date,
+    end: time::Date,
+) -> Result<Vec<(time::Date, Moon, Phenomenon, bool)>, &'static str> {
+    events::check_range(start, end)?;
+    if end.julian() - start.julian() > MAX_RANGE {
+        return Err("Searches for the phenomena of Jupiter's moons are limited to a year");
+    }
+    let mut found = Vec::new();
+    for (i, &moon) in Moon::GALILEAN.iter().enumerate() {
+        for ph in Phenomenon::ALL {
+            for d in events::roots(start, end, STEP, |d| Ok(ph.depth(i, d)))? {
+                found.push((d, moon, ph, true));
+            }
+            for d in events::roots(start, end, STEP, |d| Ok(-ph.depth(i, d)))? {
+                found.push((d, moon, ph, false));
+            }
+        }
+    }
+    found.sort_by(|a, b| a.0.julian().total_cmp(&b.0.julian()));
+    Ok(found)
+}
+
+/// A table of the phenomena of the Galilean moons in a range
+pub fn table(start: time::Date, end: time::Date) -> Result<Table, &'static str> {
+    Ok(Table {
+        columns: vec!["Date".into(), "Moon".into(), "Event".into()],
+        rows: phenomena(start, end)?
+            .into_iter()
+            .map(|(d, moon, ph, starts)| {
+                vec![
+                    Value::Date(d),
+                    Value::Str(moon.to_string()),
+                    Value::Str(format!("{} {}", ph, if starts { "Start" } else { "End" })),
+                ]
+            })
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn io_eclipses() {
+        let starts: Vec<f64> = phenomena(
+            time::Date::from_julian(2461330.5),
+            time::Date::from_julian(2461344.5),
+        )
+        .unwrap()
+        .into_iter()
+        .filter(|&(_, moon, ph, starts)| moon == Moon::Io && ph == Phenomenon::Eclipse && starts)
+        .map(|(d, ..)| d.julian())
+        .collect();
+        // Io goes into Jupiter's shadow once every synodic period, 1.7699 days
+        assert_eq!(starts.len(), 8);
+        for w in starts.windows(2) {
+            assert!((w[1] - w[0] - 1.7699).abs() < 0.002, "{}", w[1] - w[0]);
+        }
+    }
+}
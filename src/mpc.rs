@@ -0,0 +1,257 @@
+//! Orbital elements in the Minor Planet Center's one-line export formats
+//!
+//! Minor planets are read from the MPCORB format, and comets from the CometEls format, as described at
+//! <https://minorplanetcenter.net/iau/info/MPOrbitFormat.html> and
+//! <https://minorplanetcenter.net/iau/info/CometOrbitFormat.html>. Both are fixed width, and the format of a
+//! line is told by the packed epoch that MPCORB lines have in columns 21–25, or the year of perihelion that
+//! CometEls lines have in columns 15–18.
+
+use crate::orbit::{self, Magnitude, Orbit};
+use pracstro::time;
+
+/// Slope parameter for minor planets that don't have one
+const DEFAULT_SLOPE: f64 = 0.15;
+
+/// A field of a line, between the first and last columns (counted from 1) the MPC documents it in
+fn column(line: &str, first: usize, last: usize) -> Option<&str> {
+    line.get(first - 1..last.min(line.len())).map(str::trim)
+}
+
+/// A number from a field, which must be there
+fn number(line: &str, first: usize, last: usize, err: &'static str) -> Result<f64, &'static str> {
+    column(line, first, last)
+        .and_then(|s| s.parse::<f64>().ok())
+        .filter(|n| n.is_finite())
+        .ok_or(err)
+}
+
+/// A date packed into five characters, such as `K239D` for 2023-09-13
+fn packed_date(s: &str) -> Result<time::Date, &'static str> {
+    fn digit(c: char) -> Option<u8> {
+        match c {
+            '1'..='9' => c.to_digit(10).map(|d| d as u8),
+            'A'..='V' => Some(c as u8 - b'A' + 10),
+            _ => None,
+        }
+    }
+    let c: Vec<char> = s.chars().collect();
+    let [century, y1, y2, m, d] = c[..] else {
+        return Err("Bad epoch");
+    };
+    let century: i64 = match century {
+        'I' => 18,
+        'J' => 19,
+        'K' => 20,
+        _ => return Err("Bad epoch"),
+    };
+    let year = format!("{}{}", y1, y2)
+        .parse::<i64>()
+        .map_err(|_| "Bad epoch")?;
+    let (month, day) = (digit(m).ok_or("Bad epoch")?, digit(d).ok_or("Bad epoch")?);
+    if month > 12 {
+        return Err("Bad epoch");
+    }
+    Ok(time::Date::from_calendar(
+        century * 100 + year,
+        month,
+        day,
+        time::Angle::default(),
+    ))
+}
+
+/// The names an object can be looked up by, all in lowercase
+///
+/// Minor planets are named like `(1) Ceres` and comets like `1P/Halley` or `C/1995 O1 (Hale-Bopp)`. Each can
+/// be found by its full designation, its number or provisional designation, and its name.
+fn names(designation: &str) -> Vec<String> {
+    let full = designation.trim().to_lowercase();
+    let mut names = vec![full.clone()];
+    if let Some((number, name)) = full.strip_prefix('(').and_then(|s| s.split_once(") ")) {
+        names.extend([number.to_string(), name.to_string()]);
+    } else if let Some((desig, name)) = full.strip_suffix(')').and_then(|s| s.split_once(" (")) {
+        names.extend([desig.to_string(), name.to_string()]);
+    } else if let Some((desig, name)) = full.split_once('/') {
+        // Numbered periodic comets, the provisional designations of others are only unique with their prefix
+        if desig.ends_with(['p', 'd']) && desig[..desig.len() - 1].parse::<u32>().is_ok() {
+            names.extend([desig.to_string(), name.to_string()]);
+        }
+    }
+    names.dedup();
+    names
+}
+
+/// A minor planet from a line of MPCORB
+fn minor_planet(line: &str) -> Result<(Vec<String>, Orbit), &'static str> {
+    let epoch = packed_date(column(line, 21, 25).ok_or("Line too short")?)?;
+    let m = number(line, 27, 35, "Bad mean anomaly")?;
+    let peri = number(line, 38, 46, "Bad argument of perihelion")?;
+    let node = number(line, 49, 57, "Bad longitude of the ascending node")?;
+    let i = number(line, 60, 68, "Bad inclination")?;
+    let e = number(line, 71, 79, "Bad eccentricity")?;
+    let a = number(line, 93, 103, "Bad semi-major axis")?;
+    if !(0.0..1.0).contains(&e) || a <= 0.0 {
+        return Err("Orbit is not an ellipse");
+    }
+    // Some faint objects have no magnitude
+    let h = number(line, 9, 13, "").unwrap_or(f64::NAN);
+    let g = number(line, 15, 19, "").unwrap_or(DEFAULT_SLOPE);
+    let designation = column(line, 167, 194)
+        .filter(|s| !s.is_empty())
+        // The packed number, which is the number itself below 100000
+        .or(column(line, 1, 7).map(|s| s.trim_start_matches('0')))
+        .ok_or("No designation")?;
+    Ok((
+        names(designation),
+        Orbit {
+            q: a * (1.0 - e),
+            e,
+            i,
+            node,
+            peri,
+            tp: orbit::perihelion(epoch, a, m),
+            magnitude: Magnitude::Asteroid { h, g },
+        },
+    ))
+}
+
+/// A comet from a line of CometEls
+fn comet(line: &str) -> Result<(Vec<String>, Orbit), &'static str> {
+    let year = number(line, 15, 18, "Bad perihelion date")?;
+    let month = number(line, 20, 21, "Bad perihelion date")?;
+    let day = number(line, 23, 29, "Bad perihelion date")?;
+    if !(1.0..=12.0).contains(&month) || !(1.0..32.0).contains(&day) {
+        return Err("Bad perihelion date");
+    }
+    let q = number(line, 31, 39, "Bad perihelion distance")?;
+    let e = number(line, 42, 49, "Bad eccentricity")?;
+    let peri = number(line, 52, 59, "Bad argument of perihelion")?;
+    let node = number(line, 62, 69, "Bad longitude of the ascending node")?;
+    let i = number(line, 72, 79, "Bad inclination")?;
+    if q <= 0.0 || e < 0.0 {
+        return Err("Bad orbit");
+    }
+    // The slope is given as m = H + 5 log Δ + 2.5 G log r
+    let h = number(line, 92, 95, "").unwrap_or(f64::NAN);
+    let k = 2.5 * number(line, 97, 100, "").unwrap_or(4.0);
+    let designation = column(line, 103, 158)
+        .filter(|s| !s.is_empty())
+        // The packed number and type of a periodic comet, like 0001P
+        .or(column(line, 1, 12).map(|s| s.trim_start_matches('0')))
+        .ok_or("No designation")?;
+    Ok((
+        names(designation),
+        Orbit {
+            q,
+            e,
+            i,
+            node,
+            peri,
+            tp: time::Date::from_calendar(
+                year as i64,
+                month as u8,
+                day.trunc() as u8,
+                time::Angle::from_degrees(day.fract() * 360.0),
+            ),
+            magnitude: Magnitude::Comet { h, k },
+        },
+    ))
+}
+
+/// The names and orbit of the object on a line of either format
+pub fn parse_line(line: &str) -> Result<(Vec<String>, Orbit), &'static str> {
+    let year = |s: &str| s.len() == 4 && s.chars().all(|c| c.is_ascii_digit());
+    if column(line, 21, 25).is_some_and(|s| packed_date(s).is_ok()) {
+        minor_planet(line)
+    } else if column(line, 15, 18).is_some_and(year) {
+        comet(line)
+    } else {
+        Err("Not in the MPCORB or CometEls format")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CERES: &str = "00001    3.34  0.12 K239D  95.98917   73.42179   80.25496   10.58688  0.0789126  0.21410680   2.7672374  0 E2023-09   7283 125 1801-2023 0.65 M-v 30k MPCLINUX   0000 (1) Ceres                   20230913";
+    const HALLEY: &str = "0001P         1986 02  9.4590  0.587104  0.967143  111.3325   58.4201  162.2627  19860205   5.5  8.0  1P/Halley                                                MPC 63976";
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn packed_dates() {
+        let jd = |s| packed_date(s).unwrap().julian();
+        assert!(close(jd("K239D"), 2460200.5));
+        assert!(close(jd("J9611"), 2450083.5));
+        assert!(close(jd("I0511"), 2380322.5));
+        assert_eq!(packed_date("K23D1").map(|d| d.julian()), Err("Bad epoch"));
+        assert_eq!(packed_date("L2311").map(|d| d.julian()), Err("Bad epoch"));
+        assert_eq!(packed_date("K231").map(|d| d.julian()), Err("Bad epoch"));
+    }
+
+    #[test]
+    fn ceres() {
+        let (names, o) = parse_line(CERES).unwrap();
+        assert_eq!(names, ["(1) ceres", "1", "ceres"]);
+        let epoch = time::Date::from_calendar(2023, 9, 13, time::Angle::default());
+        assert!(close(
+            o.tp.julian(),
+            orbit::perihelion(epoch, 2.7672374, 95.98917).julian()
+        ));
+        assert!(close(o.q / (1.0 - o.e), 2.7672374));
+        assert!(close(o.e, 0.0789126));
+        assert!(close(o.i, 10.58688));
+        assert!(close(o.node, 80.25496));
+        assert!(close(o.peri, 73.42179));
+        assert_eq!(o.magnitude, Magnitude::Asteroid { h: 3.34, g: 0.12 });
+    }
+
+    #[test]
+    fn packed_designation() {
+        // Without the readable designation, the object is known by its packed number
+        let (names, _) = parse_line(&CERES[..160]).unwrap();
+        assert_eq!(names, ["1"]);
+    }
+
+    #[test]
+    fn halley() {
+        let (names, o) = parse_line(HALLEY).unwrap();
+        assert_eq!(names, ["1p/halley", "1p", "halley"]);
+        assert!((o.tp.julian() - 2446470.959).abs() < 1e-6);
+        assert!(close(o.q, 0.587104));
+        assert!(close(o.e, 0.967143));
+        assert!(close(o.i, 162.2627));
+        assert!(close(o.node, 58.4201));
+        assert!(close(o.peri, 111.3325));
+        assert_eq!(o.magnitude, Magnitude::Comet { h: 5.5, k: 20.0 });
+    }
+
+    #[test]
+    fn builtin_halley() {
+        let (_, o) = parse_line(HALLEY).unwrap();
+        let Some(crate::value::CelObj::Minor(builtin)) = crate::catalog::read().remove("halley")
+        else {
+            panic!("No Halley");
+        };
+        assert_eq!(builtin.magnitude, o.magnitude);
+        let d = time::Date::from_julian(2461330.5);
+        assert!((builtin.magnitude(d) - o.magnitude(d)).abs() < 0.1);
+    }
+
+    #[test]
+    fn malformed() {
+        let bad_e = CERES.replace("0.0789126", "0.07x9126");
+        assert_eq!(parse_line(&bad_e).map(|_| ()), Err("Bad eccentricity"));
+        let hyperbolic = CERES.replace("0.0789126", "1.0789126");
+        assert_eq!(
+            parse_line(&hyperbolic).map(|_| ()),
+            Err("Orbit is not an ellipse")
+        );
+        assert_eq!(
+            parse_line("# Not an orbit").map(|_| ()),
+            Err("Not in the MPCORB or CometEls format")
+        );
+    }
+}
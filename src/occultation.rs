@@ -0,0 +1,263 @@
+//! Occultations of stars and planets by the Moon, as seen from a location
+//!
+//! The Moon's parallax is up to a degree, so whether and when an occultation happens depends on where it's
+//! seen from. Times for planets are when the center of the planet is at the Moon's limb.
+//!
+//! The Moon and the objects are at their apparent places whether or not `-a` is given, since when one passes
+//! behind the other is seen with the light-time and aberration of both.
+
+use crate::catalog;
+use crate::events;
+use crate::horizon;
+use crate::lunar;
+use crate::query::{self, Property};
+use crate::table::Table;
+use crate::value::{AngView, CelObj, Place, RefFrame, Value};
+use pracstro::{coord::Coord, moon, sol, time};
+
+/// Only stars this close to the ecliptic, in degrees, can be occulted
+///
+/// The Moon's orbit is inclined 5.3°, and its parallax and semidiameter add another 1.3°.
+const MAX_LATITUDE: f64 = 7.0;
+
+/// Interval between samples of the Moon's position, in days
+///
+/// The Moon moves about 0.55° an hour, so a star can't pass behind it between samples unnoticed.
+const STEP: f64 = 1.0 / 24.0;
+
+/// Longest search either side of the middle of an occultation for the ingress and egress, in days
+const MAX_HALF_DURATION: f64 = 3.0 / 24.0;
+
+/// An occultation of a star or planet
+#[derive(Clone, Debug, PartialEq)]
+pub struct Occultation {
+    pub object: String,
+    pub magnitude: f64,
+    /// When the object disappears behind the Moon's limb
+    pub ingress: time::Date,
+    /// When it reappears
+    pub egress: time::Date,
+    /// Altitude of the Moon, in degrees, halfway through
+    pub altitude: f64,
+}
+
+/// Position of the center of the Moon seen from a location, and its semidiameter in degrees
+fn moon_at(
+    d: time::Date,
+    (lat, long): (time::Angle, time::Angle),
+    observer: &RefFrame,
+) -> Result<(Coord, f64), &'static str> {
+    let rf = RefFrame {
+        date: d,
+        place: Place::Apparent,
+        ..*observer
+    };
+    let Value::Crd(c, _) = query::property_of(&CelObj::Moon, Property::Equatorial, &rf)? else {
+        unreachable!();
+    };
+    let (c, dist) = horizon::topocentric(c, lunar::distance(d), d, lat, long, observer.elevation);
+    Ok((c, (moon::MOON.theta0 / dist).degrees() / 2.0))
+}
+
+/// Position of an object seen from a location, planets have a parallax of up to half a minute
+fn position(
+    obj: &CelObj,
+    d: time::Date,
+    (lat, long): (time::Angle, time::Angle),
+    observer: &RefFrame,
+) -> Result<Coord, &'static str> {
+    let rf = RefFrame {
+        date: d,
+        place: Place::Apparent,
+        ..*observer
+    };
+    let Value::Crd(c, _) = query::property_of(obj, Property::Equatorial, &rf)? else {
+        unreachable!();
+    };
+    Ok(match obj {
+        CelObj::Planet(p) => {
+            horizon::topocentric(c, p.distance(d), d, lat, long, observer.elevation).0
+        }
+        CelObj::Minor(o) => {
+            horizon::topocentric(c, o.distance(d), d, lat, long, observer.elevation).0
+        }
+        _ => c,
+    })
+}
+
+/// The objects the Moon can pass in front of, the planets, minor planets, and the stars near the ecliptic
+fn targets(cat: &catalog::Catalog, d: time::Date) -> Vec<(&str, &CelObj)> {
+    let mut found: Vec<(&str, &CelObj)> = cat
+        .iter()
+        .filter(|(_, obj)| match obj {
+            CelObj::Planet(p) => *p != sol::EARTH,
+            CelObj::Minor(_) => true,
+            CelObj::Star(s) => {
+                let beta = s.location(d).ecliptic(d).1;
+                beta.to_latitude().degrees().abs() < MAX_LATITUDE
+            }
+            _ => false,
+        })
+        .map(|(n, obj)| (n.as_str(), obj))
+        .collect();
+    // The catalog is a hash table, this keeps the output the same from run to run
+    found.sort_by_key(|(n, _)| *n);
+    found
+}
+
+/// The occultations of stars and planets in the catalog in a range, in order, seen from a location
+///
+/// Occultations that happen with the Moon below the horizon are left out. Only the location and elevation of
+/// the observer are used, not its date.
+pub fn occultations(
+    start: time::Date,
+    end: time::Date,
+    observer: &RefFrame,
+    cat: &catalog::Catalog,
+) -> Result<Vec<Occultation>, &'static str> {
+    events::check_range(start, end)?;
+    let ll = observer
+        .latlong
+        .ok_or("Need to specify a lat/long with -l")?;
+
+    let n = ((end.julian() - start.julian()) / STEP).ceil() as usize;
+    let at =
+        |i: usize| time::Date::from_julian((start.julian() + i as f64 * STEP).min(end.julian()));
+    let moons = (0..=n)
+        .map(|i| moon_at(at(i), ll, observer))
+        .collect::<Result<Vec<(Coord, f64)>, &'static str>>()?;
+
+    let mut found = Vec::new();
+    for (name, obj) in targets(cat, start) {
+        // Distance from the Moon's limb in degrees, negative when it's behind the Moon
+        let limb = |d: time::Date| -> Result<f64, &'static str> {
+            let (m, sd) = moon_at(d, ll, observer)?;
+            Ok(m.dist(position(obj, d, ll, observer)?).degrees() - sd)
+        };
+        let samples = (0..=n)
+            .map(|i| {
+                Ok(moons[i]
+                    .0
+                    .dist(position(obj, at(i), ll, observer)?)
+                    .degrees()
+                    - moons[i].1)
+            })
+            .collect::<Result<Vec<f64>, &'static str>>()?;
+        for i in 1..n {
+            // A closest approach within an hour's motion of the limb
+            if !(samples[i] <= samples[i - 1] && samples[i] < samples[i + 1] && samples[i] < 1.0) {
+                continue;
+            }
+            let mid = events::minimum(at(i - 1), at(i + 1), limb)?;
+            if limb(mid)? >= 0.0 {
+                continue;
+            }
+            let before = time::Date::from_julian(mid.julian() - MAX_HALF_DURATION);
+            let after = time::Date::from_julian(mid.julian() + MAX_HALF_DURATION);
+            let (Some(&ingress), Some(&egress)) = (
+                events::roots(before, mid, MAX_HALF_DURATION, |d| Ok(-limb(d)?))?.first(),
+                events::roots(mid, after, MAX_HALF_DURATION, limb)?.first(),
+            ) else {
+                continue;
+            };
+            let altitude = horizon::altaz(moon_at(mid, ll, observer)?.0, mid, ll.0, ll.1)
+                .1
+                .to_latitude()
+                .degrees();
+            if altitude < 0.0 {
+                continue;
+            }
+            let rf = RefFrame {
+                date: mid,
+                ..*observer
+            };
+            let Value::Num(magnitude) = query::property_of(obj, Property::Magnitude, &rf)? else {
+                unreachable!();
+            };
+            found.push(Occultation {
+                object: name.to_string(),
+                magnitude,
+                ingress,
+                egress,
+                altitude,
+            });
+        }
+    }
+    found.sort_by(|a, b| a.ingress.julian().total_cmp(&b.ingress.julian()));
+    Ok(found)
+}
+
+/// A table of the occultations in a range seen from a location
+pub fn table(
+    start: time::Date,
+    end: time::Date,
+    observer: &RefFrame,
+    cat: &catalog::Catalog,
+) -> Result<Table, &'static str> {
+    Ok(Table {
+        columns: vec![
+            "Ingress".into(),
+            "Egress".into(),
+            "Object".into(),
+            "Magnitude".into(),
+            "Moon Altitude".into(),
+        ],
+        rows: occultations(start, end, observer, cat)?
+            .into_iter()
+            .map(|o| {
+                vec![
+                    Value::Date(o.ingress),
+                    Value::Date(o.egress),
+                    Value::Str(o.object),
+                    Value::Num(o.magnitude),
+                    Value::Ang(time::Angle::from_degrees(o.altitude), AngView::Angle),
+                ]
+            })
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::Observer;
+
+    #[test]
+    fn saturn_2024() {
+        let mut rf = RefFrame {
+            latlong: Some((
+                time::Angle::from_degrees(51.5),
+                time::Angle::from_degrees(-0.1),
+            )),
+            elevation: 0.0,
+            pressure: 1010.0,
+            temperature: 10.0,
+            place: Place::Mean,
+            observer: Observer::Earth,
+            date: time::Date::from_julian(2460542.5),
+        };
+        let cat = catalog::read();
+        let (start, end) = (
+            time::Date::from_julian(2460542.5),
+            time::Date::from_julian(2460544.5),
+        );
+        let found = occultations(start, end, &rf, &cat).unwrap();
+        assert_eq!(found.len(), 1);
+        // From London, Saturn goes behind the Moon at 03:23 UT on 2024-08-21 and comes out at 04:14
+        let o = &found[0];
+        assert_eq!(o.object, "saturn");
+        assert!(
+            (o.ingress.julian() - 2460543.6410).abs() < 0.002,
+            "{}",
+            o.ingress.julian()
+        );
+        assert!(
+            (o.egress.julian() - 2460543.6764).abs() < 0.002,
+            "{}",
+            o.egress.julian()
+        );
+
+        rf.place = Place::Apparent;
+        assert_eq!(occultations(start, end, &rf, &cat).unwrap(), found);
+    }
+}
@@ -0,0 +1,160 @@
+//! Minor planets and comets, from their orbital elements
+//!
+//! Positions come from two-body motion around the Sun, without the pull of the planets, so elements are good
+//! to arcminutes for a year or two around their epoch, and less for comets that have passed close to Jupiter
+//! since.
+
+use pracstro::{coord, sol, time};
+use std::f64::consts::PI;
+
+/// Gaussian gravitational constant, the mean motion of a body 1 AU from the Sun in radians per day
+const GAUSS: f64 = 0.01720209895;
+
+/// Obliquity of the ecliptic at J2000, the elements are referred to the J2000 ecliptic
+const OBLIQUITY_J2000: f64 = 23.43928;
+
+/// How bright an object is, at 1 AU from both the Sun and the Earth
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Magnitude {
+    /// Absolute magnitude and slope of the phase curve of an asteroid (the IAU H, G system)
+    Asteroid { h: f64, g: f64 },
+    /// Absolute total magnitude of a comet, and how quickly it brightens near the Sun
+    ///
+    /// The magnitude is H + 5 log Δ + K log r.
+    Comet { h: f64, k: f64 },
+}
+
+/// A body moving around the Sun on a Keplerian orbit
+#[derive(Clone, Debug, PartialEq)]
+pub struct Orbit {
+    /// Perihelion distance (AU)
+    pub q: f64,
+    /// Eccentricity, 1 or more for parabolic and hyperbolic orbits
+    pub e: f64,
+    /// Inclination (Degrees)
+    pub i: f64,
+    /// Longitude of the ascending node (Degrees)
+    pub node: f64,
+    /// Argument of perihelion (Degrees)
+    pub peri: f64,
+    /// Time of perihelion passage
+    pub tp: time::Date,
+    pub magnitude: Magnitude,
+}
+
+/// Time of perihelion passage from the semi-major axis (AU) and the mean anomaly (Degrees) at an epoch
+///
+/// Elements for asteroids are given this way, rather than with a perihelion time.
+pub fn perihelion(epoch: time::Date, a: f64, m: f64) -> time::Date {
+    time::Date::from_julian(epoch.julian() - m.to_radians() / (GAUSS / a.powf(1.5)))
+}
+
+/// Solves `f(x) = 0` by Newton's method from `x`, `step` giving the correction f(x)/f′(x)
+fn newton(mut x: f64, step: impl Fn(f64) -> f64) -> f64 {
+    for _ in 0..100 {
+        let dx = step(x);
+        x -= dx;
+        if dx.abs() < 1e-12 {
+            break;
+        }
+    }
+    x
+}
+
+impl Orbit {
+    /// Distance from the Sun (AU) and true anomaly (Radians) at a date
+    fn anomaly(&self, d: time::Date) -> (f64, f64) {
+        let (q, e) = (self.q, self.e);
+        let t = d.julian() - self.tp.julian();
+        if (e - 1.0).abs() < 1e-6 {
+            // Barker's equation for parabolic orbits
+            let w = 3.0 * GAUSS / (2.0 * q * q * q).sqrt() * t;
+            let y = (w / 2.0 + (w * w / 4.0 + 1.0).sqrt()).cbrt();
+            let s = y - 1.0 / y;
+            (q * (1.0 + s * s), 2.0 * s.atan())
+        } else if e < 1.0 {
+            let a = q / (1.0 - e);
+            let m = (GAUSS / a.powf(1.5) * t).rem_euclid(2.0 * PI);
+            let ee = newton(if e > 0.8 { PI } else { m }, |ee| {
+                (ee - e * ee.sin() - m) / (1.0 - e * ee.cos())
+            });
+            (
+                a * (1.0 - e * ee.cos()),
+                2.0 * ((1.0 + e).sqrt() * (ee / 2.0).sin())
+                    .atan2((1.0 - e).sqrt() * (ee / 2.0).cos()),
+            )
+        } else {
+            let a = q / (e - 1.0);
+            let m = GAUSS / a.powf(1.5) * t;
+            let h = newton((m / e).asinh(), |h| {
+                (e * h.sinh() - h - m) / (e * h.cosh() - 1.0)
+            });
+            (
+                a * (e * h.cosh() - 1.0),
+                2.0 * ((e + 1.0).sqrt() * (h / 2.0).sinh())
+                    .atan2((e - 1.0).sqrt() * (h / 2.0).cosh()),
+            )
+        }
+    }
+
+    /// Returns the location as rectangular coordinates relative to the Sun, in AU
+    ///
+    /// These are equatorial, referred to J2000, as [`sol::Planet::locationcart()`] is.
+    pub fn locationcart(&self, d: time::Date) -> (f64, f64, f64) {
+        let (r, v) = self.anomaly(d);
+        let (i, node) = (self.i.to_radians(), self.node.to_radians());
+        let u = self.peri.to_radians() + v;
+        let x = r * (node.cos() * u.cos() - node.sin() * u.sin() * i.cos());
+        let y = r * (node.sin() * u.cos() + node.cos() * u.sin() * i.cos());
+        let z = r * u.sin() * i.sin();
+
+        let eps = OBLIQUITY_J2000.to_radians();
+        (
+            x,
+            eps.cos() * y - eps.sin() * z,
+            eps.sin() * y + eps.cos() * z,
+        )
+    }
+
+    /// Position relative to the Earth, referred to J2000
+    pub fn location(&self, d: time::Date) -> coord::Coord {
+        let c = self.locationcart(d);
+        let e = sol::EARTH.locationcart(d);
+        coord::Coord::from_cartesian(c.0 - e.0, c.1 - e.1, c.2 - e.2)
+    }
+
+    /// Distance from the Earth, in AU
+    pub fn distance(&self, d: time::Date) -> f64 {
+        let c = self.locationcart(d);
+        let e = sol::EARTH.locationcart(d);
+        let (x, y, z) = (c.0 - e.0, c.1 - e.1, c.2 - e.2);
+        (x * x + y * y + z * z).sqrt()
+    }
+
+    /// Distance from the Sun, in AU
+    pub fn sun_distance(&self, d: time::Date) -> f64 {
+        self.anomaly(d).0
+    }
+
+    /// The angle between the Sun and the Earth seen from the object
+    pub fn phaseangle(&self, d: time::Date) -> time::Angle {
+        let (r, delta, sun) = (self.sun_distance(d), self.distance(d), sol::SUN.distance(d));
+        time::Angle::acos(
+            ((r * r + delta * delta - sun * sun) / (2.0 * r * delta)).clamp(-1.0, 1.0),
+        )
+    }
+
+    /// Visual magnitude
+    pub fn magnitude(&self, d: time::Date) -> f64 {
+        let (r, delta) = (self.sun_distance(d), self.distance(d));
+        match self.magnitude {
+            Magnitude::Asteroid { h, g } => {
+                let half = (self.phaseangle(d).radians() / 2.0).tan();
+                let phi1 = (-3.33 * half.powf(0.63)).exp();
+                let phi2 = (-1.87 * half.powf(1.22)).exp();
+                h + 5.0 * (r * delta).log10() - 2.5 * ((1.0 - g) * phi1 + g * phi2).log10()
+            }
+            Magnitude::Comet { h, k } => h + 5.0 * delta.log10() + k * r.log10(),
+        }
+    }
+}
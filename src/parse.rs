@@ -1,6 +1,8 @@
-use crate::{timestep, value};
+use crate::horizon::Twilight;
+use crate::query::Property;
+use crate::{catalog, events, galactic, text, timestep, value};
 use chrono::prelude::*;
-use pracstro::{coord, time};
+use pracstro::{coord, sol, time};
 
 fn suffix_num(s: &str, j: &str) -> Option<f64> {
     s.strip_suffix(j)?.parse::<f64>().ok()
@@ -100,7 +102,31 @@ pub fn ephemq(s: &str) -> Result<(time::Date, timestep::Step, time::Date), &'sta
     let start = eq.next().ok_or("Bad CSV")?;
     let ste = eq.next().ok_or("Bad CSV")?;
     let end = eq.next().ok_or("Bad CSV")?;
-    Ok((date(start)?, step(ste)?, date(end)?))
+    let (start, ste, end) = (date(start)?, step(ste)?, date(end)?);
+    if ste == timestep::Step::S(0.0) || ste == timestep::Step::M(chrono::Months::new(0)) {
+        return Err("Step must not be zero");
+    }
+    if end.julian() < start.julian() {
+        return Err("End of ephemeris before start");
+    }
+    Ok((start, ste, end))
+}
+
+/// A range of dates to search, as `START,END`
+pub fn range(s: &str) -> Result<(time::Date, time::Date), &'static str> {
+    let (start, end) = s.split_once(',').ok_or("Bad CSV")?;
+    let (start, end) = (date(start)?, date(end)?);
+    events::check_range(start, end)?;
+    Ok((start, end))
+}
+
+/// A calendar year, as a range of dates to search
+pub fn year(s: &str) -> Result<(time::Date, time::Date), &'static str> {
+    let y = s.parse::<i64>().map_err(|_| "Bad year")?;
+    if !(-4000..=10000).contains(&y) {
+        return Err("Year out of range");
+    }
+    Ok(events::year(y))
 }
 
 pub fn latlong(s: &str) -> Result<value::Location, &'static str> {
@@ -128,17 +154,339 @@ pub fn latlong(s: &str) -> Result<value::Location, &'static str> {
     Ok(Some((lat(lats)?, long(longs)?)))
 }
 
+/// Height above sea level in meters, with an optional unit
+pub fn elevation(s: &str) -> Result<f64, &'static str> {
+    let n: f64 = s
+        .strip_suffix('m')
+        .unwrap_or(s)
+        .trim()
+        .parse()
+        .map_err(|_| "Bad elevation")?;
+    // From the shore of the Dead Sea to well above Everest
+    if (-500.0..=10000.0).contains(&n) {
+        Ok(n)
+    } else {
+        Err("Elevation must be between -500 and 10000 meters")
+    }
+}
+
+/// Air pressure in millibars, with an optional unit
+pub fn pressure(s: &str) -> Result<f64, &'static str> {
+    let n: f64 = s
+        .strip_suffix("mbar")
+        .or_else(|| s.strip_suffix("hPa"))
+        .unwrap_or(s)
+        .trim()
+        .parse()
+        .map_err(|_| "Bad pressure")?;
+    // No air at all, up to the highest ever recorded
+    if (0.0..=1100.0).contains(&n) {
+        Ok(n)
+    } else {
+        Err("Pressure must be between 0 and 1100 millibars")
+    }
+}
+
+/// Air temperature in °C, with an optional unit
+pub fn temperature(s: &str) -> Result<f64, &'static str> {
+    let n: f64 = s
+        .strip_suffix('C')
+        .unwrap_or(s)
+        .trim()
+        .parse()
+        .map_err(|_| "Bad temperature")?;
+    if (-90.0..=60.0).contains(&n) {
+        Ok(n)
+    } else {
+        Err("Temperature must be between -90 and 60 °C")
+    }
+}
+
+pub fn place(s: &str) -> Result<value::Place, &'static str> {
+    match s {
+        "mean" | "astrometric" => Ok(value::Place::Mean),
+        "apparent" => Ok(value::Place::Apparent),
+        _ => Err("Unknown place, use mean or apparent"),
+    }
+}
+
+pub fn format(s: &str) -> Result<text::Format, &'static str> {
+    match s.to_lowercase().as_str() {
+        "text" | "txt" => Ok(text::Format::Text),
+        "ansi" => Ok(text::Format::Ansi),
+        "html" => Ok(text::Format::Html),
+        "json" => Ok(text::Format::Json),
+        "csv" => Ok(text::Format::Csv),
+        "tsv" => Ok(text::Format::Tsv),
+        _ => Err("Unknown Format"),
+    }
+}
+
+/// Decodes the percent-encoding (and `+` for space) used in query strings
+pub fn urldecode(s: &str) -> Result<String, &'static str> {
+    let mut out: Vec<u8> = Vec::with_capacity(s.len());
+    let mut bytes = s.bytes();
+    while let Some(b) = bytes.next() {
+        match b {
+            b'+' => out.push(b' '),
+            b'%' => {
+                let hex = [
+                    bytes.next().ok_or("Bad percent-encoding")?,
+                    bytes.next().ok_or("Bad percent-encoding")?,
+                ];
+                out.push(
+                    u8::from_str_radix(
+                        std::str::from_utf8(&hex).map_err(|_| "Bad percent-encoding")?,
+                        16,
+                    )
+                    .map_err(|_| "Bad percent-encoding")?,
+                );
+            }
+            _ => out.push(b),
+        }
+    }
+    String::from_utf8(out).map_err(|_| "Query is not UTF-8")
+}
+
+/// A sexagesimal or decimal angle in degrees, such as `12h30m45s`, `-12d23m`, `+12°23′45″`, or `12:30:45.2`
+///
+/// Numbers without a unit, including those separated by colons, are in hours if `hours` is set and degrees
+/// otherwise.
+pub fn sexagesimal(s: &str, hours: bool) -> Result<f64, &'static str> {
+    let s = s.trim().replace("deg", "d");
+    let (sign, s) = match s.strip_prefix('-') {
+        Some(r) => (-1.0, r),
+        None => (1.0, s.strip_prefix('+').unwrap_or(&s)),
+    };
+    let hours = if s.contains('h') {
+        true
+    } else if s.contains(['d', '°']) {
+        false
+    } else {
+        hours
+    };
+    let parts = s
+        .split(['h', 'd', '°', 'm', '′', '\'', 's', '″', '"', ':'])
+        .filter(|p| !p.is_empty())
+        .map(|p| p.parse::<f64>().map_err(|_| "Invalid Angle"))
+        .collect::<Result<Vec<f64>, &'static str>>()?;
+    let value = match parts[..] {
+        [d] => d,
+        [d, m] if (0.0..60.0).contains(&m) => d + m / 60.0,
+        [d, m, s] if (0.0..60.0).contains(&m) && (0.0..60.0).contains(&s) => {
+            d + m / 60.0 + s / 3600.0
+        }
+        _ => return Err("Invalid Angle"),
+    };
+    if value < 0.0 {
+        return Err("Invalid Angle");
+    }
+    Ok(sign * value * if hours { 15.0 } else { 1.0 })
+}
+
+/// Raw coordinates, as `LONG,LAT` with an optional `,j2000` (the default) or `,date` for the epoch
+fn raw_coordinates(
+    s: &str,
+    hours: bool,
+) -> Result<(time::Angle, time::Angle, value::Epoch), &'static str> {
+    let mut parts = s.split(',');
+    let long = sexagesimal(parts.next().ok_or("Bad CSV")?, hours)?;
+    let lat = sexagesimal(parts.next().ok_or("Bad CSV")?, false)?;
+    let epoch = match parts.next() {
+        None | Some("j2000") => value::Epoch::J2000,
+        Some("date") => value::Epoch::OfDate,
+        Some(_) => return Err("Unknown epoch, use j2000 or date"),
+    };
+    if parts.next().is_some() {
+        return Err("Bad CSV");
+    }
+    if !(0.0..360.0).contains(&long) || lat.abs() > 90.0 {
+        return Err("Coordinates out of range");
+    }
+    Ok((
+        time::Angle::from_degrees(long),
+        time::Angle::from_degrees(lat),
+        epoch,
+    ))
+}
+
+/// An object from the catalog, or raw coordinates
+///
+/// Raw coordinates are `radec:RA,DEC`, `ecl:LONG,LAT`, or `gal:L,B`, followed by `,j2000` (the default) or
+/// `,date` for the equator and equinox they're referred to, where the date is `d`. Right ascension is in hours
+/// unless given in degrees (`187.5d`).
 pub fn object(
     sm: &str,
-    cat: &std::collections::HashMap<&'static str, value::CelObj>,
+    cat: &catalog::Catalog,
+    d: time::Date,
 ) -> Result<value::CelObj, &'static str> {
     let s = sm.to_lowercase();
+    if let Some(c) = s.strip_prefix("radec:") {
+        let (ra, de, epoch) = raw_coordinates(c, true)?;
+        return Ok(value::CelObj::Crd(
+            coord::Coord::from_equatorial(ra, de),
+            epoch,
+        ));
+    };
+    if let Some(c) = s.strip_prefix("ecl:") {
+        let (lambda, beta, epoch) = raw_coordinates(c, false)?;
+        let obliquity = match epoch {
+            value::Epoch::J2000 => time::Date::from_julian(2451545.0),
+            value::Epoch::OfDate => d,
+        };
+        return Ok(value::CelObj::Crd(
+            coord::Coord::from_ecliptic(lambda, beta, obliquity),
+            epoch,
+        ));
+    };
+    if let Some(c) = s.strip_prefix("gal:") {
+        let (l, b, epoch) = raw_coordinates(c, false)?;
+        if epoch != value::Epoch::J2000 {
+            return Err("Galactic coordinates are always J2000");
+        }
+        return Ok(value::CelObj::Crd(galactic::from_galactic(l, b), epoch));
+    };
+    // The older form, with the declination first
     if s.starts_with("latlong:") {
         let ll = latlong(s.strip_prefix("latlong:").ok_or("Bad prefix")?)?
             .ok_or("Raw coordinate must not be none")?;
-        return Ok(value::CelObj::Crd(coord::Coord::from_equatorial(
-            ll.1, ll.0,
-        )));
+        return Ok(value::CelObj::Crd(
+            coord::Coord::from_equatorial(ll.1, ll.0),
+            value::Epoch::OfDate,
+        ));
     };
     cat.get(s.as_str()).cloned().ok_or("Unknown Object")
 }
+
+/// Where objects are seen from, a body in the solar system, or a point as x,y,z in AU from the Sun along the
+/// axes of the J2000 ecliptic. The Earth is the default.
+pub fn observer(
+    s: &str,
+    cat: &catalog::Catalog,
+    d: time::Date,
+) -> Result<value::Observer, &'static str> {
+    if s.is_empty() {
+        return Ok(value::Observer::Earth);
+    }
+    let point: Result<Vec<f64>, _> = s.split(',').map(|n| n.trim().parse()).collect();
+    if let Ok(&[x, y, z]) = point.as_deref() {
+        if ![x, y, z].iter().all(|n| n.is_finite()) {
+            return Err("A point must be three finite numbers");
+        }
+        return Ok(value::Observer::Point(x, y, z));
+    }
+    match object(s, cat, d)? {
+        value::CelObj::Sun => Ok(value::Observer::Sun),
+        value::CelObj::Moon => Ok(value::Observer::Moon),
+        value::CelObj::Planet(p) if p == sol::EARTH => Ok(value::Observer::Earth),
+        value::CelObj::Planet(p) => sol::PLANETS
+            .into_iter()
+            .find(|&q| *q == p)
+            .map(value::Observer::Planet)
+            .ok_or("Unknown Object"),
+        value::CelObj::PlanetMoon(m) => Ok(value::Observer::PlanetMoon(m)),
+        _ => Err(
+            "Can only see objects from the Sun, the Moon, the planets and their moons, or a point",
+        ),
+    }
+}
+
+pub fn property(sm: &str, cat: &catalog::Catalog, d: time::Date) -> Result<Property, &'static str> {
+    let s = sm.to_lowercase();
+    if let Some(o) = s.strip_prefix("angbet:") {
+        return Ok(Property::AngBet(object(o, cat, d)?));
+    };
+    match s.as_str() {
+        "equ" | "equatorial" | "radec" => Ok(Property::Equatorial),
+        "ecl" | "ecliptic" => Ok(Property::Ecliptic),
+        "gal" | "galactic" => Ok(Property::Galactic),
+        "sgal" | "supergalactic" => Ok(Property::Supergalactic),
+        "hor" | "horizontal" | "altaz" => Ok(Property::Horizontal),
+        "dist" | "distance" => Ok(Property::Distance),
+        "topoequ" | "topocentric" => Ok(Property::TopoEquatorial),
+        "topodist" => Ok(Property::TopoDistance),
+        "topoangdia" => Ok(Property::TopoAngDia),
+        "appalt" | "apparentaltitude" => Ok(Property::ApparentAltitude),
+        "airmass" => Ok(Property::Airmass),
+        "extmag" => Ok(Property::ExtMagnitude),
+        "mag" | "magnitude" => Ok(Property::Magnitude),
+        "phase" => Ok(Property::PhaseDefault),
+        "phasename" => Ok(Property::PhaseName),
+        "phaseemoji" | "emoji" => Ok(Property::PhaseEmoji),
+        "phaseangle" => Ok(Property::PhaseAngle),
+        "angdia" => Ok(Property::AngDia),
+        "illumfrac" | "illum" => Ok(Property::IllumFrac),
+        "rise" => Ok(Property::Rise),
+        "set" => Ok(Property::Set),
+        "transit" | "culmination" => Ok(Property::Transit),
+        "civildawn" => Ok(Property::Dawn(Twilight::Civil)),
+        "civildusk" => Ok(Property::Dusk(Twilight::Civil)),
+        "nauticaldawn" => Ok(Property::Dawn(Twilight::Nautical)),
+        "nauticaldusk" => Ok(Property::Dusk(Twilight::Nautical)),
+        "astrodawn" => Ok(Property::Dawn(Twilight::Astronomical)),
+        "astrodusk" => Ok(Property::Dusk(Twilight::Astronomical)),
+        "daylength" => Ok(Property::DayLength),
+        "sunlit" | "illumination" => Ok(Property::Illumination),
+        "offset" => Ok(Property::Offset),
+        _ => Err("Unknown Property"),
+    }
+}
+
+/// A CSV list of properties
+pub fn properties(
+    s: &str,
+    cat: &catalog::Catalog,
+    d: time::Date,
+) -> Result<Vec<Property>, &'static str> {
+    s.split(',').map(|p| property(p, cat, d)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sexagesimals() {
+        assert!((sexagesimal("12h30m45s", true).unwrap() - 187.6875).abs() < 1e-9);
+        assert!((sexagesimal("12:30:45", true).unwrap() - 187.6875).abs() < 1e-9);
+        assert_eq!(sexagesimal("-0d30m", false), Ok(-0.5));
+        assert_eq!(sexagesimal("187.5d", true), Ok(187.5));
+        assert!(sexagesimal("", false).is_err());
+        assert!(sexagesimal("abc", false).is_err());
+        assert!(sexagesimal("12h30x", true).is_err());
+    }
+
+    #[test]
+    fn urldecodes() {
+        assert_eq!(urldecode("%2B").as_deref(), Ok("+"));
+        assert_eq!(urldecode("a+b").as_deref(), Ok("a b"));
+        assert!(urldecode("%zz").is_err());
+        assert!(urldecode("%2").is_err());
+    }
+
+    #[test]
+    fn observer_points() {
+        let cat = std::collections::HashMap::new();
+        let d = time::Date::from_julian(2451545.0);
+        assert_eq!(
+            observer("1, 0, -0.5", &cat, d),
+            Ok(value::Observer::Point(1.0, 0.0, -0.5))
+        );
+        assert!(observer("nan,nan,nan", &cat, d).is_err());
+        assert!(observer("1,inf,0", &cat, d).is_err());
+    }
+
+    #[test]
+    fn ecliptic_of_date() {
+        let cat = std::collections::HashMap::new();
+        // Five centuries on, the obliquity is 0.065° less than J2000's
+        let d = time::Date::from_julian(2451545.0 + 5.0 * 36525.0);
+        let dec = |s| match object(s, &cat, d) {
+            Ok(value::CelObj::Crd(c, _)) => c.equatorial().1.to_latitude().degrees(),
+            o => panic!("{:?}", o),
+        };
+        let of_date = coord::mean_obliquity_ecl(d).degrees();
+        assert!((dec("ecl:90,0,date") - of_date).abs() < 1e-9);
+        assert!((dec("ecl:90,0") - 23.439292).abs() < 1e-9);
+    }
+}
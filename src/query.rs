@@ -1,4 +1,8 @@
+use crate::horizon::{self, Event, Twilight};
+use crate::table::Table;
+use crate::timestep::EphemIter;
 use crate::value::*;
+use crate::{apparent, heliocentric, lunar, moons, satellite};
 use pracstro::{coord::Coord, moon, sol, time};
 use std::fmt;
 
@@ -6,16 +10,38 @@ use std::fmt;
 pub enum Property {
     Equatorial,
     Ecliptic,
+    Galactic,
+    Supergalactic,
+    /// Seen from the surface of the Earth rather than its center, as are horizontal coordinates
+    Horizontal,
+    TopoEquatorial,
     Distance,
+    TopoDistance,
     Magnitude,
     PhaseDefault,
     PhaseName,
     PhaseEmoji,
     PhaseAngle,
     AngDia,
+    TopoAngDia,
+    /// Altitude with refraction, where the object is seen
+    ApparentAltitude,
+    Airmass,
+    /// Magnitude dimmed by extinction in the atmosphere
+    ExtMagnitude,
     IllumFrac,
     Rise,
     Set,
+    Transit,
+    /// The start of morning twilight, for the Sun
+    Dawn(Twilight),
+    /// The end of evening twilight, for the Sun
+    Dusk(Twilight),
+    DayLength,
+    /// Whether a satellite is in sunlight or in the Earth's shadow
+    Illumination,
+    /// Where a moon is relative to its planet
+    Offset,
     AngBet(CelObj),
 }
 impl fmt::Display for Property {
@@ -26,7 +52,12 @@ impl fmt::Display for Property {
             match self {
                 Property::Equatorial => "Coordinates (RA/De)",
                 Property::Ecliptic => "Coordinates (Ecliptic)",
+                Property::Galactic => "Coordinates (Galactic)",
+                Property::Supergalactic => "Coordinates (Supergalactic)",
+                Property::Horizontal => "Coordinates (Alt/Az)",
+                Property::TopoEquatorial => "Topocentric (RA/De)",
                 Property::Distance => "Distance",
+                Property::TopoDistance => "Topocentric Distance",
                 Property::Magnitude => "Magnitude",
                 Property::PhaseDefault => "Phase",
                 Property::PhaseEmoji => "Phase Emoji",
@@ -34,14 +65,80 @@ impl fmt::Display for Property {
                 Property::PhaseAngle => "Phase Angle",
                 Property::IllumFrac => "Illuminated Frac.",
                 Property::AngDia => "Angular Diameter",
+                Property::TopoAngDia => "Topocentric Ang. Dia.",
+                Property::ApparentAltitude => "Apparent Altitude",
+                Property::Airmass => "Airmass",
+                Property::ExtMagnitude => "Extincted Magnitude",
                 Property::Rise => "Rise Time",
                 Property::Set => "Set Time",
+                Property::Transit => "Transit Time",
+                Property::Dawn(Twilight::Civil) => "Civil Dawn",
+                Property::Dusk(Twilight::Civil) => "Civil Dusk",
+                Property::Dawn(Twilight::Nautical) => "Nautical Dawn",
+                Property::Dusk(Twilight::Nautical) => "Nautical Dusk",
+                Property::Dawn(Twilight::Astronomical) => "Astronomical Dawn",
+                Property::Dusk(Twilight::Astronomical) => "Astronomical Dusk",
+                Property::DayLength => "Day Length",
+                Property::Illumination => "Illumination",
+                Property::Offset => "Offset from Planet",
                 Property::AngBet(_) => "Angle Between Object",
             }
         )
     }
 }
 
+/// Position of an object seen from the observer's location, and its distance in AU if it's known
+///
+/// The parallax is largest for the Moon, up to a degree.
+fn topocentric(obj: &CelObj, rf: &RefFrame) -> Result<(Coord, Option<f64>), &'static str> {
+    let (lat, long) = rf.latlong.ok_or("Need to specify a lat/long with -l")?;
+    let Value::Crd(c, _) = property_of(obj, Property::Equatorial, rf)? else {
+        unreachable!();
+    };
+    Ok(match property_of(obj, Property::Distance, rf) {
+        Ok(Value::Dist(dist)) => {
+            let (c, dist) = horizon::topocentric(c, dist, rf.date, lat, long, rf.elevation);
+            (c, Some(dist))
+        }
+        _ => (c, None),
+    })
+}
+
+/// Why a property isn't known for the observer in `rf`, if it isn't
+pub fn unavailable(q: &Property, rf: &RefFrame) -> Option<&'static str> {
+    if rf.observer == Observer::Earth {
+        return None;
+    }
+    match q {
+        Property::Horizontal
+        | Property::TopoEquatorial
+        | Property::TopoDistance
+        | Property::TopoAngDia
+        | Property::ApparentAltitude
+        | Property::Airmass
+        | Property::ExtMagnitude
+        | Property::Rise
+        | Property::Set
+        | Property::Transit
+        | Property::Dawn(_)
+        | Property::Dusk(_)
+        | Property::DayLength => Some("The horizon is only for observers on the Earth"),
+        Property::Magnitude => Some("Magnitudes are only known from the Earth"),
+        // Everything worked out from the position
+        Property::Equatorial
+        | Property::Ecliptic
+        | Property::Galactic
+        | Property::Supergalactic
+        | Property::Offset
+        | Property::AngBet(_)
+            if rf.place == Place::Apparent =>
+        {
+            Some("Apparent places are only for observers on the Earth")
+        }
+        _ => None,
+    }
+}
+
 pub fn property_of(obj: &CelObj, q: Property, rf: &RefFrame) -> Result<Value, &'static str> {
     fn hemisphere(ll: Option<(pracstro::time::Angle, pracstro::time::Angle)>) -> bool {
         if let Some((lat, _)) = ll {
@@ -50,57 +147,190 @@ pub fn property_of(obj: &CelObj, q: Property, rf: &RefFrame) -> Result<Value, &'
             false
         }
     }
+    if let Some(e) = unavailable(&q, rf) {
+        return Err(e);
+    }
+    let elsewhere = rf.observer != Observer::Earth;
     match (q, obj.clone()) {
-        (Property::Equatorial, CelObj::Planet(p)) => {
-            Ok(Value::Crd(p.location(rf.date), CrdView::Equatorial))
+        (_, CelObj::Planet(p)) if p == sol::EARTH && !elsewhere => {
+            Err("Can't see the Earth from itself")
         }
-        (Property::Equatorial, CelObj::Sun) => Ok(Value::Crd(
-            sol::SUN
-                .location(rf.date)
+        // Seen from anywhere else, the solar system is worked out with vectors from the Sun, and stars don't move
+        (
+            Property::Equatorial,
+            CelObj::Sun
+            | CelObj::Planet(_)
+            | CelObj::Minor(_)
+            | CelObj::Moon
+            | CelObj::Satellite(_)
+            | CelObj::PlanetMoon(_),
+        ) if elsewhere => Ok(Value::Crd(
+            heliocentric::location(obj, rf)?,
+            CrdView::Equatorial,
+        )),
+        (
+            Property::Distance,
+            CelObj::Sun
+            | CelObj::Planet(_)
+            | CelObj::Minor(_)
+            | CelObj::Moon
+            | CelObj::Satellite(_)
+            | CelObj::PlanetMoon(_),
+        ) if elsewhere => Ok(Value::Dist(heliocentric::distance(obj, rf)?)),
+        (
+            Property::AngDia,
+            CelObj::Sun | CelObj::Planet(_) | CelObj::Moon | CelObj::PlanetMoon(_),
+        ) if elsewhere => Ok(Value::Ang(heliocentric::angdia(obj, rf)?, AngView::Angle)),
+        (
+            Property::PhaseDefault,
+            CelObj::Planet(_) | CelObj::Minor(_) | CelObj::Moon | CelObj::PlanetMoon(_),
+        ) if elsewhere => Ok(Value::Phase(
+            heliocentric::phaseangle(obj, rf)?,
+            PhaseView::Default(false),
+        )),
+        (Property::Equatorial, _) if rf.place == Place::Apparent => {
+            Ok(Value::Crd(apparent::place(obj, rf)?, CrdView::Equatorial))
+        }
+        // The orbital elements are referred to the J2000 ecliptic
+        (Property::Equatorial, CelObj::Planet(p)) => Ok(Value::Crd(
+            p.location(rf.date)
                 .precess(time::Date::from_julian(2451545.0), rf.date),
             CrdView::Equatorial,
         )),
-        (Property::Equatorial, CelObj::Moon) => Ok(Value::Crd(
-            moon::MOON
+        (Property::Equatorial, CelObj::Minor(o)) => Ok(Value::Crd(
+            o.location(rf.date)
+                .precess(time::Date::from_julian(2451545.0), rf.date),
+            CrdView::Equatorial,
+        )),
+        (Property::Equatorial, CelObj::Sun) => Ok(Value::Crd(
+            sol::SUN
                 .location(rf.date)
                 .precess(time::Date::from_julian(2451545.0), rf.date),
             CrdView::Equatorial,
         )),
+        // The lunar theory is referred to the equinox of date
+        (Property::Equatorial, CelObj::Moon) => {
+            Ok(Value::Crd(lunar::location(rf.date), CrdView::Equatorial))
+        }
         (Property::Equatorial, CelObj::Star(s)) => Ok(Value::Crd(
-            s.loc_j2k
+            s.location(rf.date)
                 .precess(time::Date::from_julian(2451545.0), rf.date),
             CrdView::Equatorial,
         )),
-        (Property::Equatorial, CelObj::Crd(s)) => Ok(Value::Crd(s, CrdView::Equatorial)),
+        (Property::Equatorial, CelObj::Crd(s, Epoch::J2000)) => Ok(Value::Crd(
+            s.precess(time::Date::from_julian(2451545.0), rf.date),
+            CrdView::Equatorial,
+        )),
+        // SGP4 positions are referred to the true equator and mean equinox of date, within a few meters of the mean
+        (Property::Equatorial, CelObj::Satellite(s)) => Ok(Value::Crd(
+            satellite::geocentric(&s, rf.date)?.0,
+            CrdView::Equatorial,
+        )),
+        (Property::Equatorial, CelObj::PlanetMoon(m)) => {
+            let Value::Crd(p, _) =
+                property_of(&CelObj::Planet(m.planet()), Property::Equatorial, rf)?
+            else {
+                unreachable!();
+            };
+            Ok(Value::Crd(moons::shift(p, m, rf.date), CrdView::Equatorial))
+        }
+        (Property::Equatorial, CelObj::Crd(s, Epoch::OfDate)) => {
+            Ok(Value::Crd(s, CrdView::Equatorial))
+        }
         (Property::Ecliptic, _) => {
             let Value::Crd(p, _) = property_of(obj, Property::Equatorial, rf)? else {
                 unreachable!();
             };
             Ok(Value::Crd(p, CrdView::Ecliptic(rf.date)))
         }
-        (Property::Rise, _) => {
-            if rf.latlong.is_none() {
-                return Err("Need to specify a lat/long with -l");
+        (Property::Galactic, _) => {
+            let Value::Crd(p, _) = property_of(obj, Property::Equatorial, rf)? else {
+                unreachable!();
             };
+            Ok(Value::Crd(p, CrdView::Galactic(rf.date)))
+        }
+        (Property::Supergalactic, _) => {
             let Value::Crd(p, _) = property_of(obj, Property::Equatorial, rf)? else {
                 unreachable!();
             };
-            match p.riseset(rf.date, rf.latlong.unwrap().0, rf.latlong.unwrap().1) {
-                Some((x, _)) => Ok(Value::RsTime(Some(time::Date::from_time(rf.date, x)))),
-                None => Ok(Value::RsTime(None)),
-            }
+            Ok(Value::Crd(p, CrdView::Supergalactic(rf.date)))
+        }
+        (Property::TopoEquatorial, _) => {
+            Ok(Value::Crd(topocentric(obj, rf)?.0, CrdView::Equatorial))
         }
-        (Property::Set, _) => {
-            if rf.latlong.is_none() {
-                return Err("Need to specify a lat/long with -l");
+        (Property::Horizontal, _) => {
+            let (lat, long) = rf.latlong.ok_or("Need to specify a lat/long with -l")?;
+            Ok(Value::Crd(
+                topocentric(obj, rf)?.0,
+                CrdView::Horizontal(rf.date, lat, long),
+            ))
+        }
+        (Property::ApparentAltitude, _) => {
+            let (lat, long) = rf.latlong.ok_or("Need to specify a lat/long with -l")?;
+            let alt = horizon::altaz(topocentric(obj, rf)?.0, rf.date, lat, long)
+                .1
+                .to_latitude()
+                .degrees();
+            Ok(Value::Ang(
+                time::Angle::from_degrees(
+                    alt + horizon::refraction(alt, rf.pressure, rf.temperature),
+                ),
+                AngView::Latitude,
+            ))
+        }
+        // Not a number below the horizon, so an ephemeris can go through the night
+        (Property::Airmass, _) => {
+            let Value::Ang(alt, _) = property_of(obj, Property::ApparentAltitude, rf)? else {
+                unreachable!();
             };
-            let Value::Crd(p, _) = property_of(obj, Property::Equatorial, rf)? else {
+            Ok(Value::Num(
+                horizon::airmass(alt.to_latitude().degrees()).unwrap_or(f64::NAN),
+            ))
+        }
+        (Property::ExtMagnitude, _) => {
+            let Value::Ang(alt, _) = property_of(obj, Property::ApparentAltitude, rf)? else {
                 unreachable!();
             };
-            match p.riseset(rf.date, rf.latlong.unwrap().0, rf.latlong.unwrap().1) {
-                Some((_, y)) => Ok(Value::RsTime(Some(time::Date::from_time(rf.date, y)))),
-                None => Ok(Value::RsTime(None)),
-            }
+            let Value::Num(mag) = property_of(obj, Property::Magnitude, rf)? else {
+                unreachable!();
+            };
+            Ok(Value::Num(
+                mag + horizon::extinction(alt.to_latitude().degrees(), rf.pressure)
+                    .unwrap_or(f64::NAN),
+            ))
+        }
+        (Property::TopoDistance, _) => match topocentric(obj, rf)?.1 {
+            Some(dist) => Ok(Value::Dist(dist)),
+            // The reason the distance isn't known
+            None => property_of(obj, Property::Distance, rf),
+        },
+        (Property::TopoAngDia, _) => {
+            let Value::Ang(angdia, _) = property_of(obj, Property::AngDia, rf)? else {
+                unreachable!();
+            };
+            let (Value::Dist(geo), Value::Dist(topo)) = (
+                property_of(obj, Property::Distance, rf)?,
+                property_of(obj, Property::TopoDistance, rf)?,
+            ) else {
+                unreachable!();
+            };
+            Ok(Value::Ang(angdia / (topo / geo), AngView::Angle))
+        }
+        (Property::Rise | Property::Set | Property::Transit, CelObj::Satellite(_)) => {
+            Err("Satellites rise and set several times a day, list their passes instead")
+        }
+        (Property::Rise, _) => Ok(Value::RsTime(horizon::event(obj, rf, Event::Rise)?)),
+        (Property::Set, _) => Ok(Value::RsTime(horizon::event(obj, rf, Event::Set)?)),
+        (Property::Transit, _) => Ok(Value::RsTime(horizon::event(obj, rf, Event::Transit)?)),
+        (Property::Dawn(t), CelObj::Sun) => {
+            Ok(Value::RsTime(horizon::event(obj, rf, Event::Dawn(t))?))
+        }
+        (Property::Dusk(t), CelObj::Sun) => {
+            Ok(Value::RsTime(horizon::event(obj, rf, Event::Dusk(t))?))
+        }
+        (Property::DayLength, CelObj::Sun) => Ok(Value::Duration(horizon::time_above(obj, rf)?)),
+        (Property::Dawn(_) | Property::Dusk(_) | Property::DayLength, _) => {
+            Err("Twilight and day length are only for the Sun")
         }
         (Property::AngBet(c), _) => {
             let Value::Crd(p, _) = property_of(obj, Property::Equatorial, rf)? else {
@@ -112,12 +342,21 @@ pub fn property_of(obj: &CelObj, q: Property, rf: &RefFrame) -> Result<Value, &'
             Ok(Value::Ang(p.dist(o), AngView::Angle))
         }
         (Property::Distance, CelObj::Planet(p)) => Ok(Value::Dist(p.distance(rf.date))),
-        (Property::Distance, CelObj::Sun) => Ok(Value::Dist(sol::SUN.distance(rf.date))),
-        (Property::Distance, CelObj::Moon) => Ok(Value::Dist(moon::MOON.distance(rf.date))),
-        (Property::Distance, CelObj::Star(s)) => {
-            Ok(Value::Dist((1.0 / (s.pi.degrees() * 3600.0)) * 206_265.0))
+        (Property::Distance, CelObj::Minor(o)) => Ok(Value::Dist(o.distance(rf.date))),
+        (Property::Distance, CelObj::Satellite(s)) => {
+            Ok(Value::Dist(satellite::geocentric(&s, rf.date)?.1))
         }
+        (Property::Distance, CelObj::PlanetMoon(m)) => Ok(Value::Dist(moons::distance(m, rf.date))),
+        (Property::Distance, CelObj::Sun) => Ok(Value::Dist(sol::SUN.distance(rf.date))),
+        (Property::Distance, CelObj::Moon) => Ok(Value::Dist(lunar::distance(rf.date))),
+        (Property::Distance, CelObj::Star(s)) => Ok(Value::Dist(
+            s.distance(rf.date).ok_or("Distance of star not known")?,
+        )),
         (Property::Magnitude, CelObj::Planet(p)) => Ok(Value::Num(p.magnitude(rf.date))),
+        (Property::Magnitude, CelObj::Minor(o)) => Ok(Value::Num(o.magnitude(rf.date))),
+        (Property::Magnitude, CelObj::PlanetMoon(m)) => {
+            Ok(Value::Num(moons::magnitude(m, rf.date)))
+        }
         (Property::Magnitude, CelObj::Star(s)) => Ok(Value::Num(s.mag)),
         (Property::Magnitude, CelObj::Sun) => Ok(Value::Num(sol::SUN.magnitude(rf.date))),
         (Property::Magnitude, CelObj::Moon) => Ok(Value::Num(moon::MOON.magnitude(rf.date))),
@@ -125,8 +364,17 @@ pub fn property_of(obj: &CelObj, q: Property, rf: &RefFrame) -> Result<Value, &'
             p.phaseangle(rf.date),
             PhaseView::Default(hemisphere(rf.latlong)),
         )),
+        // Phases are measured as the Moon's are, from new, and pracstro does the same for the outer planets
+        (Property::PhaseDefault, CelObj::Minor(o)) => Ok(Value::Phase(
+            time::Angle::from_degrees(180.0) + o.phaseangle(rf.date),
+            PhaseView::Default(hemisphere(rf.latlong)),
+        )),
+        // Seen from the Earth, a moon's phase is its planet's
+        (Property::PhaseDefault, CelObj::PlanetMoon(m)) => {
+            property_of(&CelObj::Planet(m.planet()), Property::PhaseDefault, rf)
+        }
         (Property::PhaseDefault, CelObj::Moon) => Ok(Value::Phase(
-            moon::MOON.phaseangle(rf.date),
+            lunar::phaseangle(rf.date),
             PhaseView::Default(hemisphere(rf.latlong)),
         )),
         (Property::PhaseEmoji, _) => {
@@ -160,29 +408,77 @@ pub fn property_of(obj: &CelObj, q: Property, rf: &RefFrame) -> Result<Value, &'
         }
         (Property::AngDia, CelObj::Planet(p)) => Ok(Value::Ang(p.angdia(rf.date), AngView::Angle)),
         (Property::AngDia, CelObj::Sun) => Ok(Value::Ang(sol::SUN.angdia(rf.date), AngView::Angle)),
-        (Property::AngDia, CelObj::Moon) => {
-            Ok(Value::Ang(moon::MOON.angdia(rf.date), AngView::Angle))
+        (Property::AngDia, CelObj::Moon) => Ok(Value::Ang(
+            moon::MOON.theta0 / lunar::distance(rf.date),
+            AngView::Angle,
+        )),
+        (Property::AngDia, CelObj::PlanetMoon(m)) => {
+            Ok(Value::Ang(moons::angdia(m, rf.date), AngView::Angle))
+        }
+        (Property::Offset, CelObj::PlanetMoon(m)) => {
+            let Value::Crd(c, _) = property_of(obj, Property::Equatorial, rf)? else {
+                unreachable!();
+            };
+            let Value::Crd(p, _) =
+                property_of(&CelObj::Planet(m.planet()), Property::Equatorial, rf)?
+            else {
+                unreachable!();
+            };
+            Ok(Value::Crd(c, CrdView::Offset(p)))
         }
+        (Property::Offset, _) => Err("Offsets are only for moons of planets"),
+        (Property::Illumination, CelObj::Satellite(s)) => {
+            Ok(satellite::illumination(satellite::sunlit(&s, rf.date)?))
+        }
+        (Property::Illumination, _) => Err("Illumination is only for satellites"),
+        (Property::PhaseDefault, CelObj::Satellite(_)) => Err("Can't get phase of a satellite"),
         (Property::PhaseDefault, _) => Err("Can't get phase of a star"),
-        (_, CelObj::Crd(_)) => Err("Can't get that property for a raw coordinate"),
+        (_, CelObj::Crd(..)) => Err("Can't get that property for a raw coordinate"),
         (Property::AngDia, CelObj::Star(_)) => Err("Angular diameter of star not known"),
+        (Property::Magnitude, CelObj::Satellite(_)) => Err("Magnitude of satellite not known"),
+        (Property::AngDia, CelObj::Satellite(_)) => Err("Angular diameter of satellite not known"),
+        (Property::AngDia, CelObj::Minor(_)) => {
+            Err("Angular diameter of minor planet or comet not known")
+        }
     }
 }
 
-/// An object and a CSV list of properties. The return stack is these properties.
+/// An object and a CSV list of properties. The return stack is these properties, with the ones that aren't known
+/// for the observer as [`Value::Unavailable`].
 pub fn run(
     object: &CelObj,
     proplist: &[Property],
-    latlong: Location,
-    date: time::Date,
+    rf: &RefFrame,
 ) -> Result<Vec<Value>, &'static str> {
-    Ok(proplist
+    proplist
         .iter()
-        .map(|prop| {
-            property_of(object, prop.clone(), &RefFrame { latlong, date })
-                .unwrap_or_else(|e| panic!("Error on property {prop}: {e}"))
+        .map(|prop| match unavailable(prop, rf) {
+            Some(reason) => Ok(Value::Unavailable(reason)),
+            None => property_of(object, prop.clone(), rf),
         })
-        .collect())
+        .collect()
+}
+
+/// The longest ephemeris that will be generated, so that a typo in the step can't run forever
+pub const MAX_EPHEM_ROWS: usize = 10_000;
+
+/// An ephemeris, with a row of properties for each date in the iterator, seen by the observer in `rf`
+pub fn ephemeris(
+    object: &CelObj,
+    proplist: &[Property],
+    rf: &RefFrame,
+    dates: EphemIter,
+) -> Result<Table, &'static str> {
+    let mut rows = Vec::new();
+    for date in dates {
+        if rows.len() == MAX_EPHEM_ROWS {
+            return Err("Ephemeris has too many rows, use a larger step");
+        }
+        let mut row = vec![Value::Date(date)];
+        row.extend(run(object, proplist, &RefFrame { date, ..*rf })?);
+        rows.push(row);
+    }
+    Ok(Table::new(proplist, rows))
 }
 
 /// All the data needed for the CGI Display
@@ -193,40 +489,109 @@ pub struct CGIData {
     pub location: Coord,
     pub angdia: Option<time::Angle>,
     pub phaseangle: Option<f64>,
+    /// Airmass and extincted magnitude, if there's a location and the object is above the horizon
+    pub extinction: Option<(f64, f64)>,
 }
 
 /// Generate all the data CGI needs
-pub fn generate_cgi_data(object: &CelObj, date: time::Date) -> CGIData {
+///
+/// The position, distance, and angular diameter are topocentric if there's a location.
+pub fn generate_cgi_data(object: &CelObj, rf: &RefFrame) -> Result<CGIData, &'static str> {
     let mut data: CGIData = CGIData::default();
-    let rf = RefFrame {
-        date,
-        latlong: None,
+    let (equatorial, distance, angdia) = if rf.latlong.is_some() {
+        (
+            Property::TopoEquatorial,
+            Property::TopoDistance,
+            Property::TopoAngDia,
+        )
+    } else {
+        (Property::Equatorial, Property::Distance, Property::AngDia)
     };
-    if let Ok(Value::Dist(dist)) = property_of(object, Property::Distance, &rf) {
+    // Some stars have no usable parallax
+    if let Ok(Value::Dist(dist)) = property_of(object, distance, rf) {
         data.dist = dist;
     } else {
-        unreachable!()
+        data.dist = f64::NAN;
     }
-    if let Ok(Value::Num(brightness)) = property_of(object, Property::Magnitude, &rf) {
+    // Satellites' magnitudes aren't known
+    if let Ok(Value::Num(brightness)) = property_of(object, Property::Magnitude, rf) {
         data.brightness = brightness;
     } else {
-        unreachable!()
+        data.brightness = f64::NAN;
     }
-    if let Ok(Value::Ang(angdia, _)) = property_of(object, Property::AngDia, &rf) {
+    if let Ok(Value::Ang(angdia, _)) = property_of(object, angdia, rf) {
         data.angdia = Some(angdia);
     } else {
         data.angdia = None;
     }
-    if let Ok(Value::Crd(location, _)) = property_of(object, Property::Equatorial, &rf) {
+    if let Value::Crd(location, _) = property_of(object, equatorial, rf)? {
         data.location = location;
     } else {
         unreachable!()
     }
-    if let Ok(Value::Phase(phaseangle, _)) = property_of(object, Property::PhaseAngle, &rf) {
+    if let Ok(Value::Phase(phaseangle, _)) = property_of(object, Property::PhaseAngle, rf) {
         data.phaseangle = Some(phaseangle.radians())
     } else {
         data.phaseangle = None
     }
+    if let (Ok(Value::Num(airmass)), Ok(Value::Num(extmag))) = (
+        property_of(object, Property::Airmass, rf),
+        property_of(object, Property::ExtMagnitude, rf),
+    ) {
+        data.extinction = Some((airmass, extmag)).filter(|_| !airmass.is_nan());
+    }
+
+    Ok(data)
+}
 
-    data
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unavailable_elsewhere() {
+        let rf = RefFrame {
+            latlong: None,
+            elevation: 0.0,
+            pressure: 1010.0,
+            temperature: 10.0,
+            place: Place::Mean,
+            observer: Observer::Planet(&sol::MARS),
+            date: time::Date::from_julian(2461330.5),
+        };
+        let values = run(
+            &CelObj::Planet(sol::EARTH),
+            &[Property::Distance, Property::Magnitude, Property::Rise],
+            &rf,
+        )
+        .unwrap();
+        assert!(matches!(values[0], Value::Dist(d) if (d - 1.55).abs() < 0.01));
+        assert_eq!(
+            values[1],
+            Value::Unavailable("Magnitudes are only known from the Earth")
+        );
+        assert!(matches!(values[2], Value::Unavailable(_)));
+        assert!(property_of(&CelObj::Planet(sol::EARTH), Property::Magnitude, &rf).is_err());
+
+        let apparent = RefFrame {
+            place: Place::Apparent,
+            ..rf
+        };
+        let values = run(
+            &CelObj::Planet(sol::EARTH),
+            &[
+                Property::Equatorial,
+                Property::Ecliptic,
+                Property::Galactic,
+                Property::AngBet(CelObj::Sun),
+                Property::Distance,
+            ],
+            &apparent,
+        )
+        .unwrap();
+        assert!(values[..4].iter().all(
+            |v| *v == Value::Unavailable("Apparent places are only for observers on the Earth")
+        ));
+        assert!(matches!(values[4], Value::Dist(_)));
+    }
 }
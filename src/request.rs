@@ -0,0 +1,325 @@
+//! A request for a report, as read from either the command line or the CGI environment
+
+use crate::table::Table;
+use crate::text::{Format, TextAtom};
+use crate::value::{CelObj, Location, Observer, Place, RefFrame, Value};
+use crate::{
+    catalog, conjunction, eclipse, events, json, moons, occultation, parse, query, satellite, tile,
+    timestep,
+};
+use pracstro::time;
+use std::fmt;
+
+/// The kinds of output that can be requested
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    /// The tiled report
+    Report,
+    /// A list of properties, one per line
+    Query,
+    /// A table of properties over a range of dates
+    Ephem,
+    /// The phases of the Moon over a range of dates
+    Phases,
+    /// The equinoxes and solstices over a range of dates
+    Seasons,
+    /// Solar and lunar eclipses over a range of dates
+    Eclipses,
+    /// Conjunctions of two objects, or the oppositions and elongations of a planet
+    Conjunctions,
+    /// Occultations of stars and planets by the Moon, seen from a location
+    Occultations,
+    /// The passes of a satellite over a location
+    Passes,
+    /// The transits, occultations, and eclipses of Jupiter's moons over a range of dates
+    Jovian,
+}
+
+/// The properties in an ephemeris when none are given
+pub const DEFAULT_EPHEM_PROPERTIES: &str = "equ,dist,mag";
+
+/// Everything needed to produce some output
+#[derive(Clone, Debug, PartialEq)]
+pub struct Request {
+    pub mode: Mode,
+    pub format: Format,
+    pub object: String,
+    /// The second object, for conjunctions
+    pub other: String,
+    /// A CSV list of properties, for queries
+    pub properties: String,
+    /// Print the alternate (machine-readable) form of values
+    pub raw: bool,
+    /// Start, step, and end of an ephemeris
+    pub ephem: Option<(time::Date, timestep::Step, time::Date)>,
+    /// Start and end of an event search, which otherwise depends on the date
+    pub range: Option<(time::Date, time::Date)>,
+    pub date: time::Date,
+    pub latlong: Location,
+    /// Height of the observer above sea level, in meters
+    pub elevation: f64,
+    /// Air pressure in millibars and temperature in °C, for refraction
+    pub pressure: f64,
+    pub temperature: f64,
+    /// Mean or apparent positions
+    pub place: Place,
+    /// Where objects are seen from, a body or a point, the Earth if it's empty
+    pub observer: String,
+    /// Files of minor planet and comet elements to add to the catalog
+    pub elements: Vec<String>,
+    /// Files of two-line element sets of satellites to add to the catalog
+    pub satellites: Vec<String>,
+}
+
+/// Why a request couldn't be answered, in CGI mode these become the status of the response
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    BadRequest(String),
+    NotFound(String),
+}
+impl Error {
+    /// The status line for a CGI response
+    pub fn status(&self) -> &'static str {
+        match self {
+            Error::BadRequest(_) => "400 Bad Request",
+            Error::NotFound(_) => "404 Not Found",
+        }
+    }
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::BadRequest(e) | Error::NotFound(e) => write!(f, "{}", e),
+        }
+    }
+}
+impl From<&str> for Error {
+    fn from(e: &str) -> Self {
+        Error::BadRequest(e.into())
+    }
+}
+
+/// The catalog, with the minor planets, comets, and satellites from the request's element files
+fn catalog(req: &Request) -> Result<catalog::Catalog, Error> {
+    let builtin = catalog::read();
+    let mut cat = builtin.clone();
+    let files = req
+        .elements
+        .iter()
+        .map(|p| catalog::read_elements(p, &builtin))
+        .chain(
+            req.satellites
+                .iter()
+                .map(|p| catalog::read_tle(p, &builtin)),
+        );
+    for file in files {
+        let (bodies, errors) = file.map_err(Error::BadRequest)?;
+        // In CGI mode these go to the server's error log
+        for e in errors {
+            eprintln!("cgi-ephem: {}", e);
+        }
+        cat.extend(bodies);
+    }
+    Ok(cat)
+}
+
+/// Prints a table of events found by a search, over the range requested or else `default`
+fn events(
+    req: &Request,
+    objects: Vec<json::Json>,
+    default: (time::Date, time::Date),
+    table: impl Fn(time::Date, time::Date) -> Result<Table, &'static str>,
+) -> Result<String, Error> {
+    let (start, end) = req.range.unwrap_or(default);
+    let table = table(start, end)?;
+    match req.format {
+        Format::Json => Ok(json::events(objects, start, end, &table).to_string() + "\n"),
+        Format::Csv | Format::Tsv => Ok(table.delimited(req.format)),
+        _ => Ok(table.render(&req.format.driver(), req.raw)),
+    }
+}
+
+/// Produces the full output for a request
+pub fn run(req: &Request) -> Result<String, Error> {
+    if !req.observer.is_empty() && !matches!(req.mode, Mode::Report | Mode::Query | Mode::Ephem) {
+        return Err(
+            "Only reports, queries, and ephemerides can be seen from somewhere else".into(),
+        );
+    }
+    let mut rf = RefFrame {
+        date: req.date,
+        latlong: req.latlong,
+        elevation: req.elevation,
+        pressure: req.pressure,
+        temperature: req.temperature,
+        place: req.place,
+        observer: Observer::Earth,
+    };
+    // Searches for events aren't about a single object
+    match req.mode {
+        // The next of each phase
+        Mode::Phases => {
+            return events(
+                req,
+                Vec::new(),
+                (
+                    req.date,
+                    time::Date::from_julian(req.date.julian() + events::SYNODIC_MONTH),
+                ),
+                events::lunar_phase_table,
+            );
+        }
+        Mode::Seasons => {
+            return events(
+                req,
+                Vec::new(),
+                events::year(req.date.calendar().0),
+                events::season_table,
+            );
+        }
+        // The next year
+        Mode::Eclipses => {
+            return events(
+                req,
+                Vec::new(),
+                (
+                    req.date,
+                    time::Date::from_julian(req.date.julian() + 365.25),
+                ),
+                |start, end| eclipse::table(start, end, &rf),
+            );
+        }
+        // The next three days, there are several events a day
+        Mode::Jovian => {
+            return events(
+                req,
+                Vec::new(),
+                (req.date, time::Date::from_julian(req.date.julian() + 3.0)),
+                moons::table,
+            );
+        }
+        // The next year
+        Mode::Occultations => {
+            let cat = catalog(req)?;
+            return events(
+                req,
+                Vec::new(),
+                (
+                    req.date,
+                    time::Date::from_julian(req.date.julian() + 365.25),
+                ),
+                |start, end| occultation::table(start, end, &rf, &cat),
+            );
+        }
+        _ => (),
+    }
+
+    let cat = catalog(req)?;
+    let obj = parse::object(&req.object, &cat, req.date).map_err(|e| match e {
+        "Unknown Object" => Error::NotFound(format!("The object {} does not exist", req.object)),
+        _ => Error::BadRequest(e.into()),
+    })?;
+    rf.observer = parse::observer(&req.observer, &cat, req.date).map_err(|e| match e {
+        "Unknown Object" => Error::NotFound(format!("The object {} does not exist", req.observer)),
+        _ => Error::BadRequest(e.into()),
+    })?;
+    if rf.observer != Observer::Earth && rf.latlong.is_some() {
+        return Err("A location is on the Earth, it can't be given with another observer".into());
+    }
+    if rf.observer != Observer::Earth && rf.place == Place::Apparent {
+        return Err("Apparent places are only for observers on the Earth".into());
+    }
+    let driver = req.format.driver();
+    match req.mode {
+        Mode::Report => match req.format {
+            Format::Json => Ok(json::report(&req.object, &obj, &rf).to_string() + "\n"),
+            Format::Csv | Format::Tsv => Err("CSV and TSV are not available for reports".into()),
+            _ => Ok(tile::report(&driver, &req.object, &obj, &rf)?),
+        },
+        Mode::Query => {
+            let props = parse::properties(&req.properties, &cat, req.date)?;
+            let values = query::run(&obj, &props, &rf)?;
+            match req.format {
+                Format::Json => {
+                    Ok(json::query(&req.object, &obj, &rf, &props, &values).to_string() + "\n")
+                }
+                Format::Csv | Format::Tsv => {
+                    let mut row = vec![Value::Date(rf.date)];
+                    row.extend(values);
+                    Ok(Table::new(&props, vec![row]).delimited(req.format))
+                }
+                _ => Ok(format!(
+                    "{}{}{}",
+                    driver.header,
+                    values
+                        .iter()
+                        .map(|v| {
+                            (driver.render_atom)(TextAtom {
+                                content: if req.raw {
+                                    format!("{:#}", v)
+                                } else {
+                                    v.to_string()
+                                },
+                                special_formatting: None,
+                            }) + driver.eol
+                        })
+                        .collect::<String>(),
+                    driver.footer
+                )),
+            }
+        }
+        Mode::Ephem => {
+            let (start, step, end) = req.ephem.ok_or("No ephemeris range specified")?;
+            let props = parse::properties(&req.properties, &cat, req.date)?;
+            let table = query::ephemeris(
+                &obj,
+                &props,
+                &rf,
+                timestep::EphemIter::new(start, step, end),
+            )?;
+            match req.format {
+                Format::Json => {
+                    Ok(json::ephemeris(&req.object, &obj, &rf, &table).to_string() + "\n")
+                }
+                Format::Csv | Format::Tsv => Ok(table.delimited(req.format)),
+                _ => Ok(table.render(&driver, req.raw)),
+            }
+        }
+        // The next year
+        Mode::Conjunctions => {
+            let other = match req.other.as_str() {
+                "" => None,
+                o => Some(parse::object(o, &cat, req.date).map_err(|e| match e {
+                    "Unknown Object" => Error::NotFound(format!("The object {} does not exist", o)),
+                    _ => Error::BadRequest(e.into()),
+                })?),
+            };
+            let mut objects = vec![json::object(&req.object, &obj)];
+            objects.extend(other.as_ref().map(|o| json::object(&req.other, o)));
+            events(
+                req,
+                objects,
+                (
+                    req.date,
+                    time::Date::from_julian(req.date.julian() + 365.25),
+                ),
+                |start, end| conjunction::table(&obj, other.as_ref(), start, end),
+            )
+        }
+        // The next week
+        Mode::Passes => {
+            let CelObj::Satellite(sat) = &obj else {
+                return Err("Passes are only for satellites".into());
+            };
+            events(
+                req,
+                vec![json::object(&req.object, &obj)],
+                (req.date, time::Date::from_julian(req.date.julian() + 7.0)),
+                |start, end| satellite::table(sat, start, end, &rf),
+            )
+        }
+        Mode::Phases | Mode::Seasons | Mode::Eclipses | Mode::Occultations | Mode::Jovian => {
+            unreachable!()
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! Earth satellites seen from the ground, whether they're in sunlight, and their passes over an observer
+//!
+//! Satellites in low orbits are a few hundred kilometers up, so where they're seen in the sky depends on where
+//! they're seen from by tens of degrees, and they cross it several times a day. Their rises and sets are found as
+//! passes over a location, rather than once a day as for other objects.
+
+use crate::events;
+use crate::horizon;
+use crate::sgp4::{self, Satellite};
+use crate::table::Table;
+use crate::value::{AngView, RefFrame, Value};
+use pracstro::{coord::Coord, sol, time};
+
+/// Kilometers in an AU
+const AU: f64 = 149597870.7;
+
+/// Interval between samples of a satellite's altitude, in days
+///
+/// Passes of satellites in low orbits last several minutes, so only the lowest, grazing ones can be missed.
+const STEP: f64 = 1.0 / 1440.0;
+
+/// The longest range passes can be searched over, in days, elements aren't good for much longer than this anyway
+const MAX_RANGE: f64 = 31.0;
+
+/// Position relative to the center of the Earth, and distance from it in AU
+///
+/// The position is referred to the equator and equinox of date.
+pub fn geocentric(sat: &Satellite, d: time::Date) -> Result<(Coord, f64), &'static str> {
+    let (x, y, z) = sat.position(d)?;
+    Ok((
+        Coord::from_cartesian(x, y, z),
+        (x * x + y * y + z * z).sqrt() / AU,
+    ))
+}
+
+/// Whether a satellite is in sunlight, rather than in the Earth's shadow
+///
+/// The shadow is taken as a cylinder as wide as the Earth, which is within a few seconds of the edge of the
+/// umbra for satellites in low orbits.
+pub fn sunlit(sat: &Satellite, d: time::Date) -> Result<bool, &'static str> {
+    let (x, y, z) = sat.position(d)?;
+    let (ra, de) = sol::SUN
+        .location(d)
+        .precess(time::Date::from_julian(2451545.0), d)
+        .equatorial();
+    // Distance along the direction of the Sun, and the square of the distance from the line to it
+    let along = x * de.cos() * ra.cos() + y * de.cos() * ra.sin() + z * de.sin();
+    let across = x * x + y * y + z * z - along * along;
+    Ok(along > 0.0 || across > sgp4::EARTH_RADIUS * sgp4::EARTH_RADIUS)
+}
+
+/// Azimuth and altitude of a satellite seen from a location, in degrees
+fn altaz(
+    sat: &Satellite,
+    d: time::Date,
+    (lat, long): (time::Angle, time::Angle),
+    elevation: f64,
+) -> Result<(f64, f64), &'static str> {
+    let (c, dist) = geocentric(sat, d)?;
+    let (c, _) = horizon::topocentric(c, dist, d, lat, long, elevation);
+    let (az, alt) = horizon::altaz(c, d, lat, long);
+    Ok((az.degrees(), alt.to_latitude().degrees()))
+}
+
+/// A satellite's pass over the observer, from when it rises above the horizon to when it sets
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pass {
+    pub rise: time::Date,
+    /// Azimuth at rise, in degrees
+    pub rise_azimuth: f64,
+    /// When the satellite is highest
+    pub culmination: time::Date,
+    /// Altitude at culmination, in degrees
+    pub altitude: f64,
+    pub set: time::Date,
+    /// Azimuth at set, in degrees
+    pub set_azimuth: f64,
+    /// Whether the satellite is in sunlight at culmination
+    pub sunlit: bool,
+}
+
+/// The passes of a satellite over the observer in a range, in order
+///
+/// Rises and sets are when the satellite's center crosses the horizon, without refraction. Passes that are
+/// under way at the start or end of the range are left out. Only the location and elevation of the observer
+/// are used, not its date.
+pub fn passes(
+    sat: &Satellite,
+    start: time::Date,
+    end: time::Date,
+    observer: &RefFrame,
+) -> Result<Vec<Pass>, &'static str> {
+    events::check_range(start, end)?;
+    if end.julian() - start.julian() > MAX_RANGE {
+        return Err("Searches for passes are limited to a month");
+    }
+    let ll = observer
+        .latlong
+        .ok_or("Need to specify a lat/long with -l")?;
+    let altitude = |d: time::Date| Ok(altaz(sat, d, ll, observer.elevation)?.1);
+
+    let rises = events::roots(start, end, STEP, altitude)?;
+    let sets = events::roots(start, end, STEP, |d| Ok(-altitude(d)?))?;
+    let mut found = Vec::new();
+    for rise in rises {
+        let Some(&set) = sets.iter().find(|s| s.julian() > rise.julian()) else {
+            break;
+        };
+        let culmination = events::minimum(rise, set, |d| Ok(-altitude(d)?))?;
+        found.push(Pass {
+            rise,
+            rise_azimuth: altaz(sat, rise, ll, observer.elevation)?.0,
+            culmination,
+            altitude: altitude(culmination)?,
+            set,
+            set_azimuth: altaz(sat, set, ll, observer.elevation)?.0,
+            sunlit: sunlit(sat, culmination)?,
+        });
+    }
+    Ok(found)
+}
+
+/// Whether a satellite is lit, as a value
+pub fn illumination(lit: bool) -> Value {
+    Value::Str(if lit { "sunlit" } else { "eclipsed" }.into())
+}
+
+/// A table of the passes of a satellite in a range seen from a location
+pub fn table(
+    sat: &Satellite,
+    start: time::Date,
+    end: time::Date,
+    observer: &RefFrame,
+) -> Result<Table, &'static str> {
+    Ok(Table {
+        columns: vec![
+            "Rise".into(),
+            "Rise Azimuth".into(),
+            "Culmination".into(),
+            "Altitude".into(),
+            "Set".into(),
+            "Set Azimuth".into(),
+            "Illumination".into(),
+        ],
+        rows: passes(sat, start, end, observer)?
+            .into_iter()
+            .map(|p| {
+                vec![
+                    Value::Date(p.rise),
+                    Value::Ang(time::Angle::from_degrees(p.rise_azimuth), AngView::Angle),
+                    Value::Date(p.culmination),
+                    Value::Ang(time::Angle::from_degrees(p.altitude), AngView::Angle),
+                    Value::Date(p.set),
+                    Value::Ang(time::Angle::from_degrees(p.set_azimuth), AngView::Angle),
+                    illumination(p.sunlit),
+                ]
+            })
+            .collect(),
+    })
+}
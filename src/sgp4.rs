@@ -0,0 +1,511 @@
+//! Earth satellites, from two-line element sets propagated with SGP4
+//!
+//! This is the near-Earth part of SGP4 as revised by Vallado et al. (Revisiting Spacetrack Report #3, 2006),
+//! with the WGS-72 constants that elements are fitted with. Deep-space orbits (SDP4), with periods of 225 minutes
+//! or more such as those of navigation and geostationary satellites, aren't handled. Elements are good to about a
+//! kilometer at their epoch, and drift by a few kilometers a day after it, more for low satellites as the drag on
+//! them changes.
+
+use pracstro::time;
+use std::f64::consts::PI;
+
+/// Equatorial radius of the Earth in WGS-72, in km
+pub const EARTH_RADIUS: f64 = 6378.135;
+
+/// Square root of the Earth's gravitational constant in WGS-72, in Earth radii^1.5 per minute
+const XKE: f64 = 0.0743669161331734;
+
+/// Zonal harmonics of the Earth's gravity in WGS-72
+const J2: f64 = 0.001082616;
+const J3: f64 = -0.00000253881;
+const J4: f64 = -0.00000165597;
+
+/// Satellites with periods this long, in minutes, need the deep-space perturbations of the Sun and Moon
+const DEEP_SPACE_PERIOD: f64 = 225.0;
+
+/// A satellite's mean elements, and the constants of its secular and periodic perturbations
+#[derive(Clone, Debug, PartialEq)]
+pub struct Satellite {
+    /// The NORAD catalog number
+    pub number: u32,
+    pub epoch: time::Date,
+    /// Drag term (per Earth radius)
+    bstar: f64,
+    /// Eccentricity
+    ecc: f64,
+    /// Inclination, argument of perigee, right ascension of the ascending node, and mean anomaly (Radians)
+    incl: f64,
+    argp: f64,
+    node: f64,
+    mo: f64,
+    /// Mean motion, with the Kozai J2 term removed (Radians per minute)
+    no: f64,
+    /// Perigees under 220 km leave out the higher order drag terms
+    simple: bool,
+    aycof: f64,
+    con41: f64,
+    cc1: f64,
+    cc4: f64,
+    cc5: f64,
+    d2: f64,
+    d3: f64,
+    d4: f64,
+    delmo: f64,
+    eta: f64,
+    argpdot: f64,
+    omgcof: f64,
+    sinmao: f64,
+    t2cof: f64,
+    t3cof: f64,
+    t4cof: f64,
+    t5cof: f64,
+    x1mth2: f64,
+    x7thm1: f64,
+    mdot: f64,
+    nodedot: f64,
+    xlcof: f64,
+    xmcof: f64,
+    nodecf: f64,
+}
+
+/// A field of a line, between the first and last columns (counted from 1)
+fn column(line: &str, first: usize, last: usize) -> Result<&str, &'static str> {
+    line.get(first - 1..last)
+        .map(str::trim)
+        .ok_or("Line too short")
+}
+
+fn number(line: &str, first: usize, last: usize, err: &'static str) -> Result<f64, &'static str> {
+    column(line, first, last)?
+        .parse::<f64>()
+        .ok()
+        .filter(|n| n.is_finite())
+        .ok_or(err)
+}
+
+/// A number with an assumed leading decimal point and a power of ten, such as ` 28098-4` for 0.28098e-4
+fn exponential(s: &str) -> Result<f64, &'static str> {
+    let s = s.trim();
+    let split = s.len().checked_sub(2).ok_or("Bad drag term")?;
+    let (mantissa, exponent) = s.split_at_checked(split).ok_or("Bad drag term")?;
+    let (sign, digits) = match mantissa.strip_prefix('-') {
+        Some(d) => (-1.0, d),
+        None => (1.0, mantissa.trim_start_matches('+')),
+    };
+    let mantissa = format!("0.{}", digits)
+        .parse::<f64>()
+        .map_err(|_| "Bad drag term")?;
+    let exponent = exponent.parse::<i32>().map_err(|_| "Bad drag term")?;
+    Ok(sign * mantissa * 10.0_f64.powi(exponent))
+}
+
+/// Checks the last digit of a line, the sum of its digits (with minus signs as 1) modulo 10
+fn checksum(line: &str) -> Result<(), &'static str> {
+    let sum: u32 = line
+        .get(..68)
+        .ok_or("Line too short")?
+        .chars()
+        .map(|c| match c {
+            '-' => 1,
+            c => c.to_digit(10).unwrap_or(0),
+        })
+        .sum();
+    match line.get(68..69).and_then(|c| c.parse::<u32>().ok()) {
+        Some(c) if c == sum % 10 => Ok(()),
+        _ => Err("Bad checksum"),
+    }
+}
+
+impl Satellite {
+    /// Reads the two lines of an element set, and sets up its propagation
+    pub fn from_tle(line1: &str, line2: &str) -> Result<Satellite, &'static str> {
+        let (line1, line2) = (line1.trim_end(), line2.trim_end());
+        if !line1.starts_with("1 ") || !line2.starts_with("2 ") {
+            return Err("Not a two-line element set");
+        }
+        checksum(line1)?;
+        checksum(line2)?;
+        let catalog = column(line1, 3, 7)?
+            .parse::<u32>()
+            .map_err(|_| "Bad catalog number")?;
+        if column(line2, 3, 7)?.parse::<u32>() != Ok(catalog) {
+            return Err("Lines are for different satellites");
+        }
+
+        // Two digit years are from 1957, when the first satellite was launched
+        let year = number(line1, 19, 20, "Bad epoch")? as i64;
+        let year = if year < 57 { 2000 + year } else { 1900 + year };
+        let day = number(line1, 21, 32, "Bad epoch")?;
+        let epoch = time::Date::from_julian(
+            time::Date::from_calendar(year, 1, 1, time::Angle::default()).julian() + day - 1.0,
+        );
+        let bstar = exponential(column(line1, 54, 61)?)?;
+
+        let incl = number(line2, 9, 16, "Bad inclination")?.to_radians();
+        let node = number(line2, 18, 25, "Bad right ascension of the node")?.to_radians();
+        let ecc = format!("0.{}", column(line2, 27, 33)?)
+            .parse::<f64>()
+            .map_err(|_| "Bad eccentricity")?;
+        let argp = number(line2, 35, 42, "Bad argument of perigee")?.to_radians();
+        let mo = number(line2, 44, 51, "Bad mean anomaly")?.to_radians();
+        let revs = number(line2, 53, 63, "Bad mean motion")?;
+        if revs <= 0.0 {
+            return Err("Bad mean motion");
+        }
+        if 1440.0 / revs >= DEEP_SPACE_PERIOD {
+            return Err("Deep-space orbits (periods of 225 minutes or more) aren't supported");
+        }
+        Ok(Satellite::new(
+            catalog,
+            epoch,
+            bstar,
+            ecc,
+            incl,
+            argp,
+            node,
+            mo,
+            revs * 2.0 * PI / 1440.0,
+        ))
+    }
+
+    /// Sets up the propagation of mean elements, as `sgp4init` does
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        number: u32,
+        epoch: time::Date,
+        bstar: f64,
+        ecc: f64,
+        incl: f64,
+        argp: f64,
+        node: f64,
+        mo: f64,
+        no_kozai: f64,
+    ) -> Satellite {
+        let x2o3 = 2.0 / 3.0;
+        let j3oj2 = J3 / J2;
+        let ss = 78.0 / EARTH_RADIUS + 1.0;
+        let qzms2t = ((120.0 - 78.0) / EARTH_RADIUS).powi(4);
+
+        // Recovers the mean motion and semi-major axis from the Kozai mean motion
+        let omeosq = 1.0 - ecc * ecc;
+        let rteosq = omeosq.sqrt();
+        let cosio = incl.cos();
+        let cosio2 = cosio * cosio;
+        let ak = (XKE / no_kozai).powf(x2o3);
+        let d1 = 0.75 * J2 * (3.0 * cosio2 - 1.0) / (rteosq * omeosq);
+        let del = d1 / (ak * ak);
+        let adel = ak * (1.0 - del * del - del * (1.0 / 3.0 + 134.0 * del * del / 81.0));
+        let del = d1 / (adel * adel);
+        let no = no_kozai / (1.0 + del);
+        let ao = (XKE / no).powf(x2o3);
+        let sinio = incl.sin();
+        let po = ao * omeosq;
+        let con42 = 1.0 - 5.0 * cosio2;
+        let con41 = -con42 - cosio2 - cosio2;
+        let posq = po * po;
+        let rp = ao * (1.0 - ecc);
+
+        let simple = rp < 220.0 / EARTH_RADIUS + 1.0;
+        // The atmosphere's density is modeled down from a height that's lower for low perigees
+        let (mut sfour, mut qzms24) = (ss, qzms2t);
+        let perigee = (rp - 1.0) * EARTH_RADIUS;
+        if perigee < 156.0 {
+            sfour = if perigee < 98.0 { 20.0 } else { perigee - 78.0 };
+            qzms24 = ((120.0 - sfour) / EARTH_RADIUS).powi(4);
+            sfour = sfour / EARTH_RADIUS + 1.0;
+        }
+        let pinvsq = 1.0 / posq;
+        let tsi = 1.0 / (ao - sfour);
+        let eta = ao * ecc * tsi;
+        let etasq = eta * eta;
+        let eeta = ecc * eta;
+        let psisq = (1.0 - etasq).abs();
+        let coef = qzms24 * tsi.powi(4);
+        let coef1 = coef / psisq.powf(3.5);
+        let cc2 = coef1
+            * no
+            * (ao * (1.0 + 1.5 * etasq + eeta * (4.0 + etasq))
+                + 0.375 * J2 * tsi / psisq * con41 * (8.0 + 3.0 * etasq * (8.0 + etasq)));
+        let cc1 = bstar * cc2;
+        let cc3 = if ecc > 1.0e-4 {
+            -2.0 * coef * tsi * j3oj2 * no * sinio / ecc
+        } else {
+            0.0
+        };
+        let x1mth2 = 1.0 - cosio2;
+        let cc4 = 2.0
+            * no
+            * coef1
+            * ao
+            * omeosq
+            * (eta * (2.0 + 0.5 * etasq) + ecc * (0.5 + 2.0 * etasq)
+                - J2 * tsi / (ao * psisq)
+                    * (-3.0 * con41 * (1.0 - 2.0 * eeta + etasq * (1.5 - 0.5 * eeta))
+                        + 0.75
+                            * x1mth2
+                            * (2.0 * etasq - eeta * (1.0 + etasq))
+                            * (2.0 * argp).cos()));
+        let cc5 = 2.0 * coef1 * ao * omeosq * (1.0 + 2.75 * (etasq + eeta) + eeta * etasq);
+
+        let cosio4 = cosio2 * cosio2;
+        let temp1 = 1.5 * J2 * pinvsq * no;
+        let temp2 = 0.5 * temp1 * J2 * pinvsq;
+        let temp3 = -0.46875 * J4 * pinvsq * pinvsq * no;
+        let mdot = no
+            + 0.5 * temp1 * rteosq * con41
+            + 0.0625 * temp2 * rteosq * (13.0 - 78.0 * cosio2 + 137.0 * cosio4);
+        let argpdot = -0.5 * temp1 * con42
+            + 0.0625 * temp2 * (7.0 - 114.0 * cosio2 + 395.0 * cosio4)
+            + temp3 * (3.0 - 36.0 * cosio2 + 49.0 * cosio4);
+        let xhdot1 = -temp1 * cosio;
+        let nodedot = xhdot1
+            + (0.5 * temp2 * (4.0 - 19.0 * cosio2) + 2.0 * temp3 * (3.0 - 7.0 * cosio2)) * cosio;
+        let xmcof = if ecc > 1.0e-4 {
+            -x2o3 * coef * bstar / eeta
+        } else {
+            0.0
+        };
+        // Avoids a division by zero for retrograde equatorial orbits
+        let xlcof = -0.25 * j3oj2 * sinio * (3.0 + 5.0 * cosio)
+            / if (cosio + 1.0).abs() > 1.5e-12 {
+                1.0 + cosio
+            } else {
+                1.5e-12
+            };
+
+        let cc1sq = cc1 * cc1;
+        let d2 = 4.0 * ao * tsi * cc1sq;
+        let temp = d2 * tsi * cc1 / 3.0;
+        let d3 = (17.0 * ao + sfour) * temp;
+        let d4 = 0.5 * temp * ao * tsi * (221.0 * ao + 31.0 * sfour) * cc1;
+
+        Satellite {
+            number,
+            epoch,
+            bstar,
+            ecc,
+            incl,
+            argp,
+            node,
+            mo,
+            no,
+            simple,
+            aycof: -0.5 * j3oj2 * sinio,
+            con41,
+            cc1,
+            cc4,
+            cc5,
+            d2,
+            d3,
+            d4,
+            delmo: (1.0 + eta * mo.cos()).powi(3),
+            eta,
+            argpdot,
+            omgcof: bstar * cc3 * argp.cos(),
+            sinmao: mo.sin(),
+            t2cof: 1.5 * cc1,
+            t3cof: d2 + 2.0 * cc1sq,
+            t4cof: 0.25 * (3.0 * d3 + cc1 * (12.0 * d2 + 10.0 * cc1sq)),
+            t5cof: 0.2
+                * (3.0 * d4 + 12.0 * cc1 * d3 + 6.0 * d2 * d2 + 15.0 * cc1sq * (2.0 * d2 + cc1sq)),
+            x1mth2,
+            x7thm1: 7.0 * cosio2 - 1.0,
+            mdot,
+            nodedot,
+            xlcof,
+            xmcof,
+            nodecf: 3.5 * omeosq * xhdot1 * cc1,
+        }
+    }
+
+    /// Position in km relative to the center of the Earth at a number of minutes from the epoch
+    ///
+    /// The axes are those of the TEME frame, the true equator and mean equinox of the date.
+    pub fn propagate(&self, t: f64) -> Result<(f64, f64, f64), &'static str> {
+        // Secular gravity and drag
+        let xmdf = self.mo + self.mdot * t;
+        let argpdf = self.argp + self.argpdot * t;
+        let nodedf = self.node + self.nodedot * t;
+        let t2 = t * t;
+        let nodem = nodedf + self.nodecf * t2;
+        let (mut mm, mut argpm) = (xmdf, argpdf);
+        let mut tempa = 1.0 - self.cc1 * t;
+        let mut tempe = self.bstar * self.cc4 * t;
+        let mut templ = self.t2cof * t2;
+        if !self.simple {
+            let delomg = self.omgcof * t;
+            let delm = self.xmcof * ((1.0 + self.eta * xmdf.cos()).powi(3) - self.delmo);
+            mm = xmdf + delomg + delm;
+            argpm = argpdf - delomg - delm;
+            let (t3, t4) = (t2 * t, t2 * t2);
+            tempa -= self.d2 * t2 + self.d3 * t3 + self.d4 * t4;
+            tempe += self.bstar * self.cc5 * (mm.sin() - self.sinmao);
+            templ += self.t3cof * t3 + t4 * (self.t4cof + t * self.t5cof);
+        }
+
+        let am = (XKE / self.no).powf(2.0 / 3.0) * tempa * tempa;
+        let em = self.ecc - tempe;
+        if !(-0.001..1.0).contains(&em) {
+            return Err("Satellite has decayed, its elements are too old");
+        }
+        let em = em.max(1.0e-6);
+        mm += self.no * templ;
+        let xlm = mm + argpm + nodem;
+        let nodem = nodem.rem_euclid(2.0 * PI);
+        let argpm = argpm.rem_euclid(2.0 * PI);
+        let xlm = xlm.rem_euclid(2.0 * PI);
+        let mm = (xlm - argpm - nodem).rem_euclid(2.0 * PI);
+        let (sinim, cosim) = self.incl.sin_cos();
+
+        // Long period periodics
+        let axnl = em * argpm.cos();
+        let temp = 1.0 / (am * (1.0 - em * em));
+        let aynl = em * argpm.sin() + temp * self.aycof;
+        let xl = mm + argpm + nodem + temp * self.xlcof * axnl;
+
+        // Kepler's equation, in terms of the eccentric longitude
+        let u = (xl - nodem).rem_euclid(2.0 * PI);
+        let mut eo1 = u;
+        let (mut sineo1, mut coseo1) = eo1.sin_cos();
+        for _ in 0..10 {
+            (sineo1, coseo1) = eo1.sin_cos();
+            let step = ((u - aynl * coseo1 + axnl * sineo1 - eo1)
+                / (1.0 - coseo1 * axnl - sineo1 * aynl))
+                .clamp(-0.95, 0.95);
+            eo1 += step;
+            if step.abs() < 1.0e-12 {
+                break;
+            }
+        }
+
+        // Short period periodics
+        let ecose = axnl * coseo1 + aynl * sineo1;
+        let esine = axnl * sineo1 - aynl * coseo1;
+        let el2 = axnl * axnl + aynl * aynl;
+        let pl = am * (1.0 - el2);
+        if pl < 0.0 {
+            return Err("Satellite has decayed, its elements are too old");
+        }
+        let rl = am * (1.0 - ecose);
+        let betal = (1.0 - el2).sqrt();
+        let temp = esine / (1.0 + betal);
+        let sinu = am / rl * (sineo1 - aynl - axnl * temp);
+        let cosu = am / rl * (coseo1 - axnl + aynl * temp);
+        let su = sinu.atan2(cosu);
+        let sin2u = (cosu + cosu) * sinu;
+        let cos2u = 1.0 - 2.0 * sinu * sinu;
+        let temp1 = 0.5 * J2 / pl;
+        let temp2 = temp1 / pl;
+
+        let mrt = rl * (1.0 - 1.5 * temp2 * betal * self.con41) + 0.5 * temp1 * self.x1mth2 * cos2u;
+        let su = su - 0.25 * temp2 * self.x7thm1 * sin2u;
+        let xnode = nodem + 1.5 * temp2 * cosim * sin2u;
+        let xinc = self.incl + 1.5 * temp2 * cosim * sinim * cos2u;
+        if mrt < 1.0 {
+            return Err("Satellite has decayed, its elements are too old");
+        }
+
+        // Orientation vectors
+        let (sinsu, cossu) = su.sin_cos();
+        let (snod, cnod) = xnode.sin_cos();
+        let (sini, cosi) = xinc.sin_cos();
+        let (xmx, xmy) = (-snod * cosi, cnod * cosi);
+        let r = mrt * EARTH_RADIUS;
+        Ok((
+            r * (xmx * sinsu + cnod * cossu),
+            r * (xmy * sinsu + snod * cossu),
+            r * sini * sinsu,
+        ))
+    }
+
+    /// Position in km relative to the center of the Earth at a date, referred to the equator and equinox of date
+    pub fn position(&self, d: time::Date) -> Result<(f64, f64, f64), &'static str> {
+        self.propagate((d.julian() - self.epoch.julian()) * 1440.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test cases from Vallado et al., Revisiting Spacetrack Report #3 (SGP4-VER.TLE and tcppver.out)
+    const SAT_00005: [&str; 2] = [
+        "1 00005U 58002B   00179.78495062  .00000023  00000-0  28098-4 0  4753",
+        "2 00005  34.2682 348.7242 1859667 331.7664  19.3264 10.82419157413667",
+    ];
+    const SAT_06251: [&str; 2] = [
+        "1 06251U 62025E   06176.82412014  .00008885  00000-0  12808-3 0  3985",
+        "2 06251  58.0579  54.0425 0030035 139.1568 221.1854 15.56387291  6774",
+    ];
+
+    /// Replaces the checksum of a line with the right one
+    fn with_checksum(line: &str) -> String {
+        let sum: u32 = line[..68]
+            .chars()
+            .map(|c| match c {
+                '-' => 1,
+                c => c.to_digit(10).unwrap_or(0),
+            })
+            .sum();
+        format!("{}{}", &line[..68], sum % 10)
+    }
+
+    fn check(tle: [&str; 2], expected: &[(f64, [f64; 3])]) {
+        let sat = Satellite::from_tle(tle[0], tle[1]).unwrap();
+        for &(t, r) in expected {
+            let (x, y, z) = sat.propagate(t).unwrap();
+            for (got, want) in [x, y, z].into_iter().zip(r) {
+                assert!(
+                    (got - want).abs() < 1e-3,
+                    "at {} min: {} km, expected {} km",
+                    t,
+                    got,
+                    want
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn vallado_00005() {
+        check(
+            SAT_00005,
+            &[
+                (0.0, [7022.46529266, -1400.08296755, 0.03995155]),
+                (360.0, [-7154.03120202, -3783.17682504, -3536.19412294]),
+                (720.0, [-7134.59340119, 6531.68641334, 3260.27186483]),
+            ],
+        );
+    }
+
+    #[test]
+    fn vallado_06251() {
+        check(
+            SAT_06251,
+            &[
+                (0.0, [3988.31022699, 5498.96657235, 0.90055879]),
+                (360.0, [4993.62642836, 2890.54969900, -3600.40145627]),
+                (720.0, [3692.60030028, -976.24265255, -5623.36447493]),
+            ],
+        );
+    }
+
+    #[test]
+    fn bad_checksum() {
+        let line1 = SAT_00005[0].replace("4753", "4754");
+        assert_eq!(
+            Satellite::from_tle(&line1, SAT_00005[1]),
+            Err("Bad checksum")
+        );
+    }
+
+    #[test]
+    fn deep_space() {
+        // Two revolutions a day, as navigation satellites go around
+        let line2 = with_checksum(&SAT_00005[1].replace("10.82419157", " 2.00563000"));
+        assert_eq!(
+            Satellite::from_tle(SAT_00005[0], &line2),
+            Err("Deep-space orbits (periods of 225 minutes or more) aren't supported")
+        );
+    }
+}
@@ -0,0 +1,200 @@
+//! Tables of values, such as ephemerides
+
+use crate::query::Property;
+use crate::text::{Driver, Format};
+use crate::value::{AngView, CrdView, PhaseView, Value};
+use crate::{galactic, horizon, moons};
+
+/// Named columns and rows of values, each row has one value per column
+#[derive(Clone, Debug, PartialEq)]
+pub struct Table {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+/// Splits a value into its raw numeric (or text) fields, as (column suffix, field)
+///
+/// Every value of a variant splits into the same columns, so that the header can be read off any row.
+fn fields(v: &Value) -> Vec<(&'static str, String)> {
+    match v {
+        Value::Date(d) | Value::RsTime(Ok(d)) => vec![
+            (" (JD)", d.julian().to_string()),
+            (" (Unix)", d.unix().to_string()),
+        ],
+        // The reason goes in place of the time, so that the columns stay the same
+        Value::RsTime(Err(e)) => vec![(" (JD)", e.name().into()), (" (Unix)", e.name().into())],
+        Value::Ang(a, AngView::Angle) => vec![(" (deg)", a.degrees().to_string())],
+        Value::Ang(a, AngView::Latitude) => {
+            vec![(" (deg)", a.to_latitude().degrees().to_string())]
+        }
+        Value::Ang(a, AngView::Time) => vec![(" (h)", a.decimal().to_string())],
+        Value::Crd(c, CrdView::Equatorial) => {
+            let (ra, de) = c.equatorial();
+            vec![
+                (" RA (h)", ra.decimal().to_string()),
+                (" Dec (deg)", de.to_latitude().degrees().to_string()),
+            ]
+        }
+        Value::Crd(c, CrdView::Ecliptic(d)) => {
+            let (lambda, beta) = c.ecliptic(*d);
+            vec![
+                (" Lon (deg)", lambda.degrees().to_string()),
+                (" Lat (deg)", beta.to_latitude().degrees().to_string()),
+            ]
+        }
+        Value::Crd(c, CrdView::Galactic(d)) => {
+            let (l, b) = galactic::galactic(*c, *d);
+            vec![
+                (" l (deg)", l.degrees().to_string()),
+                (" b (deg)", b.to_latitude().degrees().to_string()),
+            ]
+        }
+        Value::Crd(c, CrdView::Supergalactic(d)) => {
+            let (l, b) = galactic::supergalactic(*c, *d);
+            vec![
+                (" SGL (deg)", l.degrees().to_string()),
+                (" SGB (deg)", b.to_latitude().degrees().to_string()),
+            ]
+        }
+        Value::Crd(c, CrdView::Offset(from)) => {
+            let (east, north) = moons::offset(*c, *from);
+            vec![
+                (" East (arcsec)", east.to_string()),
+                (" North (arcsec)", north.to_string()),
+            ]
+        }
+        Value::Crd(c, CrdView::Horizontal(d, lat, long)) => {
+            let (az, alt) = horizon::altaz(*c, *d, *lat, *long);
+            vec![
+                (" Alt (deg)", alt.to_latitude().degrees().to_string()),
+                (" Az (deg)", az.degrees().to_string()),
+            ]
+        }
+        Value::Num(n) => vec![("", n.to_string())],
+        Value::Int(n) => vec![("", n.to_string())],
+        Value::Str(s) => vec![("", s.clone())],
+        Value::Unavailable(_) => vec![("", String::new())],
+        Value::Duration(h) => vec![(" (h)", h.to_string())],
+        Value::Dist(d) => vec![(" (AU)", d.to_string())],
+        Value::Phase(pa, PhaseView::PhaseAngle) => vec![(" (deg)", pa.degrees().to_string())],
+        Value::Phase(pa, PhaseView::Illumfrac) => {
+            vec![("", ((1.0 - pa.cos()) / 2.0).to_string())]
+        }
+        Value::Phase(pa, PhaseView::Default(_)) => vec![
+            (" Angle (deg)", pa.degrees().to_string()),
+            (
+                " Illuminated Fraction",
+                ((1.0 - pa.cos()) / 2.0).to_string(),
+            ),
+        ],
+        Value::Phase(_, PhaseView::PhaseName | PhaseView::Emoji(_)) => vec![("", v.to_string())],
+    }
+}
+
+/// Quotes a CSV field if needed, as in RFC 4180
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// TSV fields can't be quoted, so separators are replaced instead
+fn tsv_field(s: &str) -> String {
+    s.replace(['\t', '\r', '\n'], " ")
+}
+
+impl Table {
+    /// A table with a date column, then a column for each property
+    pub fn new(proplist: &[Property], rows: Vec<Vec<Value>>) -> Table {
+        let mut columns: Vec<String> = vec!["Date".into()];
+        columns.extend(proplist.iter().map(|p| p.to_string()));
+        Table { columns, rows }
+    }
+
+    /// Renders the table with a driver, `raw` uses the alternate form of values
+    pub fn render(&self, driver: &Driver, raw: bool) -> String {
+        let cells: Vec<Vec<String>> = self
+            .rows
+            .iter()
+            .map(|r| {
+                r.iter()
+                    .map(|v| {
+                        if raw {
+                            format!("{:#}", v)
+                        } else {
+                            v.to_string()
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        format!(
+            "{}{}{}",
+            driver.header,
+            (driver.render_table)(&self.columns, &cells),
+            driver.footer
+        )
+    }
+
+    /// Renders the table as CSV (RFC 4180) or TSV, with values split into raw numeric columns
+    pub fn delimited(&self, format: Format) -> String {
+        let (sep, eol, field): (&str, &str, fn(&str) -> String) = match format {
+            Format::Tsv => ("\t", "\n", tsv_field),
+            _ => (",", "\r\n", csv_field),
+        };
+        let line = |cells: Vec<String>| cells.join(sep) + eol;
+
+        let header: Vec<String> = match self.rows.first() {
+            Some(r) => self
+                .columns
+                .iter()
+                .zip(r)
+                .flat_map(|(c, v)| {
+                    fields(v)
+                        .into_iter()
+                        .map(move |(s, _)| field(&(c.clone() + s)))
+                })
+                .collect(),
+            None => self.columns.iter().map(|c| field(c)).collect(),
+        };
+
+        let mut out = line(header);
+        for r in &self.rows {
+            out.push_str(&line(
+                r.iter().flat_map(fields).map(|(_, f)| field(&f)).collect(),
+            ));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::NoEvent;
+    use pracstro::time;
+
+    #[test]
+    fn no_event_reason() {
+        let table = Table::new(
+            &[Property::Rise, Property::Set],
+            vec![vec![
+                Value::Date(time::Date::from_julian(2461330.5)),
+                Value::RsTime(Err(NoEvent::Circumpolar)),
+                Value::RsTime(Ok(time::Date::from_julian(2461330.75))),
+            ]],
+        );
+        let csv = table.delimited(Format::Csv);
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("Date (JD),Date (Unix),Rise Time (JD),Rise Time (Unix),Set Time (JD),Set Time (Unix)")
+        );
+        assert_eq!(
+            lines.next(),
+            Some("2461330.5,1792195200,circumpolar,circumpolar,2461330.75,1792216800")
+        );
+    }
+}
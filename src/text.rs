@@ -41,12 +41,68 @@ pub struct TextAtom {
 /// A set of functions for rendering formatted text in a certain format
 pub struct Driver {
     pub render_atom: fn(TextAtom) -> String,
+    /// Renders a table from its column names and rows of cells
+    pub render_table: fn(&[String], &[Vec<String>]) -> String,
     pub cgi_header: &'static str,
+    pub content_type: &'static str,
     pub header: &'static str,
     pub footer: &'static str,
     pub eol: &'static str,
 }
 
+impl Driver {
+    /// The CGI header for a response with a status other than 200
+    pub fn cgi_status_header(&self, status: &str) -> String {
+        format!(
+            "Status: {}\r\nContent-Type: {}\r\n\r\n",
+            status, self.content_type
+        )
+    }
+
+    /// A full page containing only an error message
+    pub fn error_page(&self, msg: &str) -> String {
+        format!(
+            "{}{}{}{}",
+            self.header,
+            (self.render_atom)(TextAtom {
+                content: format!("Error: {}", msg),
+                special_formatting: Some(TextFormatting {
+                    color: Some(Color(ANSIColors::Red, true)),
+                    bgcolor: None,
+                    bold: true,
+                    italic: false,
+                    underline: false,
+                }),
+            }),
+            self.eol,
+            self.footer
+        )
+    }
+}
+
+/// The output formats that can be requested
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Text,
+    Ansi,
+    Html,
+    Json,
+    Csv,
+    Tsv,
+}
+impl Format {
+    pub fn driver(self) -> Driver {
+        match self {
+            Format::Text => TEXT_DRIVER,
+            Format::Ansi => ANSI_DRIVER,
+            Format::Html => HTML_DRIVER,
+            Format::Json => JSON_DRIVER,
+            Format::Csv => CSV_DRIVER,
+            Format::Tsv => TSV_DRIVER,
+        }
+    }
+}
+
 fn render_html_color(color: Color) -> &'static str {
     match color {
         Color(ANSIColors::Black, false) => "black",
@@ -95,11 +151,31 @@ fn render_html_atom(atom: TextAtom) -> String {
     }
 }
 
+fn render_html_table(columns: &[String], rows: &[Vec<String>]) -> String {
+    fn row(cells: &[String], tag: &str) -> String {
+        format!(
+            "<tr>{}</tr>\n",
+            cells
+                .iter()
+                .map(|c| format!("<{}>{}</{}>", tag, html_escape::encode_text(c), tag))
+                .collect::<String>()
+        )
+    }
+    // The header opens a <pre> for the tiles, which can't contain a table
+    format!(
+        "</pre>\n<table>\n{}{}</table>\n<pre>",
+        row(columns, "th"),
+        rows.iter().map(|r| row(r, "td")).collect::<String>()
+    )
+}
+
 pub const HTML_DRIVER: Driver = Driver {
     render_atom: render_html_atom,
+    render_table: render_html_table,
     header: include_str!("dat/header_html"),
     footer: include_str!("dat/footer_html"),
     cgi_header: "Status: 200 OK\r\nContent-Type: text/html;charset=utf-8\r\n\r\n",
+    content_type: "text/html;charset=utf-8",
     eol: "<br>",
 };
 
@@ -137,18 +213,81 @@ fn render_ansi_atom(text: TextAtom) -> String {
     }
 }
 
+/// Lays out a table as aligned columns, with a bold header and a rule under it
+fn render_columns(
+    columns: &[String],
+    rows: &[Vec<String>],
+    render_atom: fn(TextAtom) -> String,
+) -> String {
+    let widths: Vec<usize> = (0..columns.len())
+        .map(|i| {
+            rows.iter()
+                .map(|r| r[i].chars().count())
+                .chain([columns[i].chars().count()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let line = |cells: &[String]| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(c, w)| format!("{:<w$}", c, w = w))
+            .collect::<Vec<String>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    let mut out = render_atom(TextAtom {
+        content: line(columns),
+        special_formatting: Some(TextFormatting {
+            color: None,
+            bgcolor: None,
+            bold: true,
+            italic: false,
+            underline: false,
+        }),
+    });
+    out.push('\n');
+    out.push_str(&line(
+        &widths
+            .iter()
+            .map(|w| "-".repeat(*w))
+            .collect::<Vec<String>>(),
+    ));
+    out.push('\n');
+    for r in rows {
+        out.push_str(&line(r));
+        out.push('\n');
+    }
+    out
+}
+
+fn render_ansi_table(columns: &[String], rows: &[Vec<String>]) -> String {
+    render_columns(columns, rows, render_ansi_atom)
+}
+
 pub const ANSI_DRIVER: Driver = Driver {
     render_atom: render_ansi_atom,
+    render_table: render_ansi_table,
     header: "",
     cgi_header: "Status: 200 OK\r\nContent-Type: text/plain;charset=utf-8\r\n\r\n",
+    content_type: "text/plain;charset=utf-8",
     footer: "",
     eol: "\n",
 };
 
+fn render_text_table(columns: &[String], rows: &[Vec<String>]) -> String {
+    render_columns(columns, rows, render_text_atom)
+}
+
 pub const TEXT_DRIVER: Driver = Driver {
     render_atom: render_text_atom,
+    render_table: render_text_table,
     header: "",
     cgi_header: "Status: 200 OK\r\nContent-Type: text/plain;charset=utf-8\r\n\r\n",
+    content_type: "text/plain;charset=utf-8",
     footer: "",
     eol: "\n",
 };
@@ -156,3 +295,35 @@ pub const TEXT_DRIVER: Driver = Driver {
 pub fn render_text_atom(atom: TextAtom) -> String {
     atom.content
 }
+
+/// JSON is built as a whole document instead of from atoms, this driver only provides the CGI headers
+pub const JSON_DRIVER: Driver = Driver {
+    render_atom: render_text_atom,
+    render_table: render_text_table,
+    header: "",
+    cgi_header: "Status: 200 OK\r\nContent-Type: application/json\r\n\r\n",
+    content_type: "application/json",
+    footer: "",
+    eol: "\n",
+};
+
+/// Like JSON, CSV is built from a whole table, this driver only provides the CGI headers
+pub const CSV_DRIVER: Driver = Driver {
+    render_atom: render_text_atom,
+    render_table: render_text_table,
+    header: "",
+    cgi_header: "Status: 200 OK\r\nContent-Type: text/csv;charset=utf-8;header=present\r\n\r\n",
+    content_type: "text/csv;charset=utf-8;header=present",
+    footer: "",
+    eol: "\n",
+};
+
+pub const TSV_DRIVER: Driver = Driver {
+    render_atom: render_text_atom,
+    render_table: render_text_table,
+    header: "",
+    cgi_header: "Status: 200 OK\r\nContent-Type: text/tab-separated-values;charset=utf-8\r\n\r\n",
+    content_type: "text/tab-separated-values;charset=utf-8",
+    footer: "",
+    eol: "\n",
+};
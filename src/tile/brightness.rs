@@ -109,3 +109,34 @@ pub fn brightness_tile(brightness: f64, line: usize) -> Vec<TextAtom> {
         _ => unreachable!(),
     }
 }
+
+/// The brightness tile with the airmass and the magnitude dimmed by the atmosphere, for objects above the horizon
+pub fn brightness_tile_extinction(
+    brightness: f64,
+    airmass: f64,
+    extmag: f64,
+    line: usize,
+) -> Vec<TextAtom> {
+    match line {
+        12 => vec![
+            TextAtom {
+                content: format!(
+                    "{:^53}",
+                    format!("Airmass: {:.2}, Through the Air: {:.2}", airmass, extmag)
+                ),
+                special_formatting: Some(TextFormatting {
+                    bold: false,
+                    italic: false,
+                    underline: false,
+                    color: Some(Color(ANSIColors::Blue, false)),
+                    bgcolor: None,
+                }),
+            },
+            TextAtom {
+                content: "|".into(),
+                special_formatting: None,
+            },
+        ],
+        _ => brightness_tile(brightness, line),
+    }
+}
@@ -0,0 +1,106 @@
+//! A diagram of Jupiter and its Galilean moons, as they're seen with north up and east to the left
+
+use crate::moons::{self, Moon, Phenomenon};
+use crate::text::{ANSIColors, Color, TextAtom, TextFormatting};
+use crate::tile::phase;
+use crate::value::CelObj;
+use pracstro::{sol, time};
+
+/// Width inside the box, which is as wide as two tiles
+const WIDTH: usize = 107;
+
+/// Columns per radius of Jupiter, so that Callisto at its furthest fits
+const SCALE: f64 = 1.9;
+
+/// Whether an object's report has the diagram, Jupiter's and its moons' do
+pub fn has_diagram(obj: &CelObj) -> bool {
+    match obj {
+        CelObj::Planet(p) => *p == sol::JUPITER,
+        CelObj::PlanetMoon(m) => m.planet() == sol::JUPITER,
+        _ => false,
+    }
+}
+
+/// Splits a row of characters into atoms, each colored character is its own atom
+fn atoms(row: &[(char, Option<Color>)]) -> Vec<TextAtom> {
+    let mut out: Vec<TextAtom> = Vec::new();
+    for &(c, color) in row {
+        match (color, out.last_mut()) {
+            (None, Some(last)) if last.special_formatting.is_none() => last.content.push(c),
+            _ => out.push(TextAtom {
+                content: c.to_string(),
+                special_formatting: color.map(|color| TextFormatting {
+                    color: Some(color),
+                    bgcolor: None,
+                    bold: true,
+                    italic: false,
+                    underline: false,
+                }),
+            }),
+        }
+    }
+    out
+}
+
+/// The lines of the diagram at a date
+///
+/// Moons behind Jupiter or in its shadow can't be seen, so aren't drawn. A moon right next to another, or in
+/// front of Jupiter, is drawn on the line below.
+pub fn jovian_diagram(d: time::Date) -> Vec<Vec<TextAtom>> {
+    let jupiter = Some(phase::color_of(&CelObj::Planet(sol::JUPITER)));
+    let white = Some(Color(ANSIColors::White, true));
+    let center = WIDTH / 2;
+    let mut rows = vec![vec![(' ', None); WIDTH]; 2];
+    rows[0][center - 1] = ('(', jupiter);
+    rows[0][center] = ('O', jupiter);
+    rows[0][center + 1] = (')', jupiter);
+
+    let now = moons::in_progress(d);
+    let hidden = |m: Moon| {
+        now.iter()
+            .any(|&(n, p)| n == m && matches!(p, Phenomenon::Occultation | Phenomenon::Eclipse))
+    };
+    for (&moon, (x, _, _)) in Moon::GALILEAN.iter().zip(moons::galilean(d, false)) {
+        if hidden(moon) {
+            continue;
+        }
+        let col = (center as f64 + x * SCALE)
+            .round()
+            .clamp(1.0, (WIDTH - 2) as f64) as usize;
+        let letter = (moon.to_string().chars().next().unwrap(), white);
+        match rows.iter_mut().find(|r| r[col].0 == ' ') {
+            Some(r) => r[col] = letter,
+            None => rows[1][col] = letter,
+        }
+    }
+
+    let notes: Vec<String> = now
+        .iter()
+        .map(|(m, p)| match p {
+            Phenomenon::Transit => format!("{} in transit", m),
+            Phenomenon::ShadowTransit => format!("{}'s shadow on Jupiter", m),
+            Phenomenon::Occultation => format!("{} behind Jupiter", m),
+            Phenomenon::Eclipse => format!("{} eclipsed", m),
+        })
+        .collect();
+
+    let plain = |content: String| TextAtom {
+        content,
+        special_formatting: None,
+    };
+    let boxed = |content: String| vec![plain(format!("|{:^WIDTH$}|", content))];
+    let mut lines = vec![
+        vec![plain(format!("+{:-^WIDTH$}+", " Galilean Moons "))],
+        vec![plain(format!("| {:<53}{:>52} |", "East", "West"))],
+    ];
+    for r in rows {
+        let mut line = vec![plain("|".into())];
+        line.extend(atoms(&r));
+        line.push(plain("|".into()));
+        lines.push(line);
+    }
+    lines.push(boxed("I Io   E Europa   G Ganymede   C Callisto".into()));
+    lines.push(boxed(notes.join(", ")));
+    lines.push(vec![plain(format!("+{:-<WIDTH$}+", ""))]);
+    lines
+}
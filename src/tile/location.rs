@@ -1,8 +1,8 @@
 use crate::text::TextFormatting;
 use crate::text::{self};
 use crate::text::{ANSIColors, Color};
-use crate::tile;
-use crate::value::CrdView;
+use crate::value::{CrdView, Location};
+use crate::{horizon, tile};
 use iau_constellations;
 use pracstro::coord::Coord;
 use pracstro::time;
@@ -24,7 +24,15 @@ pub fn zodiac_of(coord: Coord, date: time::Date) -> &'static str {
     ][(coord.ecliptic(date).0.degrees() / 30.0) as usize]
 }
 
-pub fn location_tile(location: Coord, line: usize, date: time::Date) -> Vec<text::TextAtom> {
+/// The location of an object, with its altitude and azimuth if the observer's location is known
+///
+/// With a location, `location` is the position seen from it.
+pub fn location_tile(
+    location: Coord,
+    line: usize,
+    date: time::Date,
+    latlong: Location,
+) -> Vec<text::TextAtom> {
     let coords_1875 = location
         .precess(
             date,
@@ -35,12 +43,18 @@ pub fn location_tile(location: Coord, line: usize, date: time::Date) -> Vec<text
         coords_1875.0.degrees(),
         coords_1875.1.to_latitude().degrees(),
     )];
+    // The first line of each section, everything moves up to make room for the altitude/azimuth
+    let (constell, equ, ecl, gal, hor) = if latlong.is_some() {
+        (1, 4, 6, 8, Some(11))
+    } else {
+        (2, 5, 8, 11, None)
+    };
     match line {
         0 => vec![text::TextAtom {
             content: format!("+{:-^53}+", " Location "),
             special_formatting: None,
         }],
-        3 => vec![
+        l if l == constell => vec![
             text::TextAtom {
                 content: format!("|{:>31}", "In the Constellation"),
                 special_formatting: None,
@@ -60,7 +74,7 @@ pub fn location_tile(location: Coord, line: usize, date: time::Date) -> Vec<text
                 special_formatting: None,
             },
         ],
-        4 => vec![
+        l if l == constell + 1 => vec![
             text::TextAtom {
                 content: "|".into(),
                 special_formatting: None,
@@ -90,28 +104,83 @@ pub fn location_tile(location: Coord, line: usize, date: time::Date) -> Vec<text
                 special_formatting: None,
             },
         ],
-        7 => vec![text::TextAtom {
-            content: format!("|{:^53}|", "Coordinates (Equatorial):"),
+        l if l == equ => vec![text::TextAtom {
+            content: format!(
+                "|{:^53}|",
+                if latlong.is_some() {
+                    "Coordinates (Topocentric):"
+                } else {
+                    "Coordinates (Equatorial):"
+                }
+            ),
             special_formatting: None,
         }],
-        8 => vec![text::TextAtom {
+        l if l == equ + 1 => vec![text::TextAtom {
             content: format!(
                 "|{:^53}|",
                 crate::value::Value::Crd(location, CrdView::Equatorial).to_string()
             ),
             special_formatting: None,
         }],
-        10 => vec![text::TextAtom {
+        l if l == ecl => vec![text::TextAtom {
             content: format!("|{:^53}|", "Coordinates (Ecliptic):"),
             special_formatting: None,
         }],
-        11 => vec![text::TextAtom {
+        l if l == ecl + 1 => vec![text::TextAtom {
             content: format!(
                 "|{:^53}|",
                 crate::value::Value::Crd(location, CrdView::Ecliptic(date)).to_string()
             ),
             special_formatting: None,
         }],
+        l if l == gal => vec![text::TextAtom {
+            content: format!("|{:^53}|", "Coordinates (Galactic):"),
+            special_formatting: None,
+        }],
+        l if l == gal + 1 => vec![text::TextAtom {
+            content: format!(
+                "|{:^53}|",
+                crate::value::Value::Crd(location, CrdView::Galactic(date)).to_string()
+            ),
+            special_formatting: None,
+        }],
+        l if Some(l) == hor => vec![text::TextAtom {
+            content: format!("|{:^53}|", "Altitude/Azimuth:"),
+            special_formatting: None,
+        }],
+        l if Some(l - 1) == hor => {
+            let (lat, long) = latlong.unwrap();
+            let (_, alt) = horizon::altaz(location, date, lat, long);
+            vec![
+                text::TextAtom {
+                    content: "|".into(),
+                    special_formatting: None,
+                },
+                text::TextAtom {
+                    content: format!(
+                        "{:^53}",
+                        crate::value::Value::Crd(location, CrdView::Horizontal(date, lat, long))
+                            .to_string()
+                            + if alt.to_latitude().degrees() > 0.0 {
+                                " (Above Horizon)"
+                            } else {
+                                " (Below Horizon)"
+                            }
+                    ),
+                    special_formatting: Some(TextFormatting {
+                        bold: true,
+                        color: None,
+                        bgcolor: None,
+                        italic: false,
+                        underline: false,
+                    }),
+                },
+                text::TextAtom {
+                    content: "|".into(),
+                    special_formatting: None,
+                },
+            ]
+        }
         14 => vec![text::TextAtom {
             content: tile::TILE_FOOTER.into(),
             special_formatting: None,
@@ -1,9 +1,12 @@
 pub mod brightness;
 pub mod distance;
+pub mod jovian;
 pub mod location;
 pub mod phase;
 
+use crate::query;
 use crate::text::{self, TextAtom};
+use crate::value::{CelObj, Observer, RefFrame};
 
 pub const EMPTY_LINE: &str = "|                                                     |";
 pub const EMPTY_LINE_NOSTART: &str = "                                                     |";
@@ -53,3 +56,96 @@ pub fn na_nostart_tile(line: usize, title: String) -> Vec<TextAtom> {
         _ => unreachable!(),
     }
 }
+
+/// Renders the full tiled report for an object
+pub fn report(
+    driver: &text::Driver,
+    name: &str,
+    obj: &CelObj,
+    rf: &RefFrame,
+) -> Result<String, &'static str> {
+    fn render(driver: &text::Driver, atoms: Vec<TextAtom>) -> String {
+        atoms.into_iter().map(driver.render_atom).collect()
+    }
+    let data = query::generate_cgi_data(obj, rf)?;
+    let mut out = String::new();
+
+    out.push_str(driver.header);
+    let from = match rf.observer {
+        Observer::Earth => String::new(),
+        o => format!(" from {}", o),
+    };
+    out.push_str(&render(
+        driver,
+        vec![TextAtom {
+            content: format!("Report for {}{} on JD{:0.2}", name, from, rf.date.julian()),
+            special_formatting: None,
+        }],
+    ));
+    out.push_str(driver.eol);
+
+    let location_tile: Vec<String> = (0..=14)
+        .map(|x| {
+            render(
+                driver,
+                location::location_tile(data.location, x, rf.date, rf.latlong),
+            )
+        })
+        .collect();
+
+    let phase_tile: Vec<String> = if let Some(phaseangle) = data.phaseangle {
+        (0..=14)
+            .map(|x| render(driver, phase::phase_tile(phaseangle, x, obj)))
+            .collect()
+    } else {
+        (0..=14)
+            .map(|x| render(driver, na_nostart_tile(x, " Phase ".into())))
+            .collect()
+    };
+
+    let brightness_tile: Vec<String> = if data.brightness.is_nan() {
+        (0..=14)
+            .map(|x| render(driver, na_nostart_tile(x, " Brightness ".into())))
+            .collect()
+    } else if let Some((airmass, extmag)) = data.extinction {
+        (0..=14)
+            .map(|x| {
+                render(
+                    driver,
+                    brightness::brightness_tile_extinction(data.brightness, airmass, extmag, x),
+                )
+            })
+            .collect()
+    } else {
+        (0..=14)
+            .map(|x| render(driver, brightness::brightness_tile(data.brightness, x)))
+            .collect()
+    };
+
+    let distance_tile: Vec<String> = (0..=14)
+        .map(|x| render(driver, distance::distance_tile(data.dist, data.angdia, x)))
+        .collect();
+
+    for i in 0..=13 {
+        out.push_str(&format!(
+            "{}{}{}",
+            location_tile[i], phase_tile[i], driver.eol
+        ));
+    }
+    for i in 0..=14 {
+        out.push_str(&format!(
+            "{}{}{}",
+            distance_tile[i], brightness_tile[i], driver.eol
+        ));
+    }
+    // The diagram is of the moons seen from the Earth
+    if jovian::has_diagram(obj) && rf.observer == Observer::Earth {
+        for line in jovian::jovian_diagram(rf.date) {
+            out.push_str(&render(driver, line));
+            out.push_str(driver.eol);
+        }
+    }
+
+    out.push_str(driver.footer);
+    Ok(out)
+}
@@ -13,9 +13,13 @@ pub fn color_of(obj: &CelObj) -> Color {
             "Jupiter" | "Saturn" => Color(ANSIColors::Yellow, false),
             "Uranus" => Color(ANSIColors::Cyan, true),
             "Neptune" => Color(ANSIColors::Blue, false),
+            "Earth" => Color(ANSIColors::Blue, true),
             "Mercury" => Color(ANSIColors::White, false),
             _ => unreachable!(),
         },
+        CelObj::Minor(_) | CelObj::Satellite(_) | CelObj::PlanetMoon(_) => {
+            Color(ANSIColors::White, false)
+        }
         _ => unreachable!(),
     }
 }
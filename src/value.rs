@@ -1,12 +1,55 @@
-use crate::catalog;
+use crate::{catalog, galactic, horizon, moons, orbit, sgp4};
 use pracstro::{coord, sol, time};
 use std::fmt;
 
 pub type Location = Option<(time::Angle, time::Angle)>;
 
+/// Which corrections are applied to positions
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Place {
+    /// The geometric position, referred to the mean equator and equinox of date
+    Mean,
+    /// Where the object is seen, with light-time, aberration, and nutation
+    Apparent,
+}
+
+/// Where objects are seen from
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Observer {
+    /// The center of the Earth, or the location on it
+    Earth,
+    Sun,
+    Moon,
+    Planet(&'static sol::Planet),
+    PlanetMoon(moons::Moon),
+    /// A point in AU from the Sun, along the axes of the J2000 ecliptic
+    Point(f64, f64, f64),
+}
+impl fmt::Display for Observer {
+    /// As it's named in the catalog
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Observer::Earth => write!(f, "earth"),
+            Observer::Sun => write!(f, "sun"),
+            Observer::Moon => write!(f, "moon"),
+            Observer::Planet(p) => write!(f, "{}", p.name.to_lowercase()),
+            Observer::PlanetMoon(m) => write!(f, "{}", m.to_string().to_lowercase()),
+            Observer::Point(x, y, z) => write!(f, "{},{},{}", x, y, z),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct RefFrame {
     pub latlong: Location,
+    /// Height of the observer above sea level, in meters
+    pub elevation: f64,
+    /// Air pressure at the observer, in millibars, for refraction
+    pub pressure: f64,
+    /// Air temperature at the observer, in °C, for refraction
+    pub temperature: f64,
+    pub place: Place,
+    pub observer: Observer,
     pub date: time::Date,
 }
 
@@ -23,6 +66,23 @@ pub enum PhaseView {
 pub enum CrdView {
     Equatorial,
     Ecliptic(time::Date),
+    /// Altitude and azimuth at a date, latitude, and longitude
+    Horizontal(time::Date, time::Angle, time::Angle),
+    /// Galactic longitude and latitude of a position referred to the equinox of a date
+    Galactic(time::Date),
+    /// Supergalactic longitude and latitude of a position referred to the equinox of a date
+    Supergalactic(time::Date),
+    /// Arcseconds east and north of another position, such as a moon's planet
+    Offset(coord::Coord),
+}
+
+/// The equator and equinox that raw coordinates are referred to
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Epoch {
+    /// J2000.0, as star catalogs are, precessed to the date when used
+    J2000,
+    /// The date they're used at
+    OfDate,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -31,7 +91,13 @@ pub enum CelObj {
     Moon,
     Sun,
     Star(catalog::Star),
-    Crd(coord::Coord),
+    /// A minor planet or comet
+    Minor(orbit::Orbit),
+    /// An Earth satellite, from a two-line element set
+    Satellite(Box<sgp4::Satellite>),
+    /// A moon of Jupiter or Saturn
+    PlanetMoon(moons::Moon),
+    Crd(coord::Coord, Epoch),
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -49,7 +115,42 @@ pub enum Value {
     Num(f64),
     Dist(f64),
     Phase(time::Angle, PhaseView),
-    RsTime(Option<time::Date>),
+    RsTime(Result<time::Date, NoEvent>),
+    /// A length of time, in hours
+    Duration(f64),
+    Int(i64),
+    /// A label, such as the name of an event
+    Str(String),
+    /// A property that isn't known for the observer, such as a magnitude seen from Mars, and why
+    Unavailable(&'static str),
+}
+
+/// Why an object doesn't rise, set, or transit on a day
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum NoEvent {
+    /// Above the horizon all day
+    Circumpolar,
+    /// Below the horizon all day
+    NeverRises,
+    /// Crosses the horizon, but not in this direction on this day (as the Moon does about once a month)
+    NotOnDay,
+    /// The Sun never gets as far below the horizon as the twilight, as in a summer far enough north
+    NoNight,
+    /// The Sun stays further below the horizon than the twilight all day, as in a polar winter
+    NoTwilight,
+}
+
+impl NoEvent {
+    /// The reason as a single word, for JSON and CSV
+    pub fn name(self) -> &'static str {
+        match self {
+            NoEvent::Circumpolar => "circumpolar",
+            NoEvent::NeverRises => "never_rises",
+            NoEvent::NotOnDay => "not_on_day",
+            NoEvent::NoNight => "no_night",
+            NoEvent::NoTwilight => "no_twilight",
+        }
+    }
 }
 
 impl fmt::Display for Value {
@@ -68,9 +169,10 @@ impl fmt::Display for Value {
         ];
 
         fn phaseidx(ilumfrac: f64, ang: time::Angle) -> usize {
-            match (ilumfrac, ang.degrees() > 90.0) {
+            // The age of the Moon is over 180° after full
+            match (ilumfrac, ang.degrees() > 180.0) {
                 (0.00..0.04, _) => 0,
-                (0.96..1.00, _) => 4,
+                (0.96..=1.00, _) => 4,
                 (0.46..0.54, true) => 6,
                 (0.46..0.54, false) => 2,
                 (0.54..0.96, true) => 5,
@@ -97,8 +199,17 @@ impl fmt::Display for Value {
                     write!(f, "{:02}°{:02}′{:02.1}″", d, m, s)
                 }
                 Value::Ang(p, AngView::Latitude) => {
-                    let (d, m, s) = p.to_latitude().degminsec();
-                    write!(f, "{:+02}°{:02}′{:02.1}″", d, m, s)
+                    // degminsec() truncates negative minutes and seconds, so the sign is handled here
+                    let lat = p.to_latitude().degrees();
+                    let (d, m, s) = time::Angle::from_degrees(lat.abs()).degminsec();
+                    write!(
+                        f,
+                        "{}{:02}°{:02}′{:02.1}″",
+                        if lat < 0.0 { '-' } else { '+' },
+                        d,
+                        m,
+                        s
+                    )
                 }
                 //Value::Per(p, AngView::Raw) => write!(f, "{:.5}", p.degrees()),
                 Value::Ang(p, AngView::Time) => {
@@ -106,6 +217,7 @@ impl fmt::Display for Value {
                     write!(f, "{:02}h{:02}m{:02}s", h, m, s.trunc())
                 }
                 Value::Dist(d) => match d {
+                    d if d.is_nan() => write!(f, "unknown"),
                     0.0..0.003342293561 => write!(f, "{:.1} km", d * 149597870.7),
                     20000.0.. => write!(f, "{:.2} ly", d / 63241.07708),
                     _ => write!(f, "{:.2} AU", d),
@@ -128,6 +240,44 @@ impl fmt::Display for Value {
                         Value::Ang(d.1, AngView::Latitude)
                     )
                 }
+                Value::Crd(c, CrdView::Horizontal(d, lat, long)) => {
+                    let (az, alt) = horizon::altaz(*c, *d, *lat, *long);
+                    write!(
+                        f,
+                        "{} {}",
+                        Value::Ang(alt, AngView::Latitude),
+                        Value::Ang(az, AngView::Angle)
+                    )
+                }
+                Value::Crd(c, CrdView::Galactic(d)) => {
+                    let (l, b) = galactic::galactic(*c, *d);
+                    write!(
+                        f,
+                        "{} {}",
+                        Value::Ang(l, AngView::Angle),
+                        Value::Ang(b, AngView::Latitude)
+                    )
+                }
+                Value::Crd(c, CrdView::Supergalactic(d)) => {
+                    let (l, b) = galactic::supergalactic(*c, *d);
+                    write!(
+                        f,
+                        "{} {}",
+                        Value::Ang(l, AngView::Angle),
+                        Value::Ang(b, AngView::Latitude)
+                    )
+                }
+                Value::Crd(c, CrdView::Offset(from)) => {
+                    let (east, north) = moons::offset(*c, *from);
+                    write!(
+                        f,
+                        "{:.1}″ {} {:.1}″ {}",
+                        east.abs(),
+                        if east < 0.0 { 'W' } else { 'E' },
+                        north.abs(),
+                        if north < 0.0 { 'S' } else { 'N' }
+                    )
+                }
                 Value::Phase(pa, PhaseView::Default(_)) => {
                     let ilf = (1.0 - pa.cos()) / 2.0;
                     let pi = phaseidx(ilf, *pa);
@@ -148,33 +298,38 @@ impl fmt::Display for Value {
                 Value::Phase(pa, PhaseView::PhaseAngle) => {
                     write!(f, "{}°", pa.degrees())
                 }
+                // Such as the airmass of an object below the horizon
+                Value::Num(n) if n.is_nan() => write!(f, "none"),
                 Value::Num(n) => write!(f, "{:0.2}", n),
-                Value::RsTime(d) => {
-                    if d.is_none() {
-                        write!(f, "none")
-                    } else {
-                        write!(
-                            f,
-                            "{}",
-                            DateTime::<Local>::from(
-                                DateTime::from_timestamp(d.unwrap().unix() as i64, 0)
-                                    .expect("Failed to Format Date")
-                            )
-                            .format("%H:%M")
-                        )
-                    }
+                Value::Int(n) => write!(f, "{}", n),
+                Value::Str(s) => write!(f, "{}", s),
+                Value::RsTime(Ok(d)) => write!(
+                    f,
+                    "{}",
+                    DateTime::<Local>::from(
+                        DateTime::from_timestamp(d.unix() as i64, 0)
+                            .expect("Failed to Format Date")
+                    )
+                    .format("%H:%M")
+                ),
+                Value::RsTime(Err(NoEvent::Circumpolar)) => write!(f, "circumpolar"),
+                Value::RsTime(Err(NoEvent::NeverRises)) => write!(f, "never rises"),
+                Value::RsTime(Err(NoEvent::NotOnDay)) => write!(f, "none"),
+                Value::RsTime(Err(NoEvent::NoNight)) => write!(f, "no night"),
+                Value::RsTime(Err(NoEvent::NoTwilight)) => write!(f, "no twilight"),
+                Value::Unavailable(_) => write!(f, "N/A"),
+                Value::Duration(h) => {
+                    let m = (h * 60.0).round() as i64;
+                    write!(f, "{}h{:02}m", m / 60, m % 60)
                 }
             }
         } else {
             match self {
                 Value::Date(d) => write!(f, "{}", d.unix()),
-                Value::RsTime(d) => {
-                    if d.is_none() {
-                        write!(f, "none")
-                    } else {
-                        write!(f, "{}", d.unwrap().unix())
-                    }
-                }
+                Value::RsTime(Ok(d)) => write!(f, "{}", d.unix()),
+                Value::RsTime(Err(_)) => write!(f, "none"),
+                Value::Unavailable(_) => write!(f, "N/A"),
+                Value::Duration(h) => write!(f, "{:.5}", h),
                 Value::Ang(p, AngView::Angle) => {
                     write!(f, "{:.5}", p.degrees())
                 }
@@ -203,6 +358,37 @@ impl fmt::Display for Value {
                         Value::Ang(d.1, AngView::Latitude)
                     )
                 }
+                Value::Crd(c, CrdView::Horizontal(d, lat, long)) => {
+                    let (az, alt) = horizon::altaz(*c, *d, *lat, *long);
+                    write!(
+                        f,
+                        "[{:#}, {:#}]",
+                        Value::Ang(alt, AngView::Latitude),
+                        Value::Ang(az, AngView::Angle)
+                    )
+                }
+                Value::Crd(c, CrdView::Galactic(d)) => {
+                    let (l, b) = galactic::galactic(*c, *d);
+                    write!(
+                        f,
+                        "[{:#}, {:#}]",
+                        Value::Ang(l, AngView::Angle),
+                        Value::Ang(b, AngView::Latitude)
+                    )
+                }
+                Value::Crd(c, CrdView::Supergalactic(d)) => {
+                    let (l, b) = galactic::supergalactic(*c, *d);
+                    write!(
+                        f,
+                        "[{:#}, {:#}]",
+                        Value::Ang(l, AngView::Angle),
+                        Value::Ang(b, AngView::Latitude)
+                    )
+                }
+                Value::Crd(c, CrdView::Offset(from)) => {
+                    let (east, north) = moons::offset(*c, *from);
+                    write!(f, "[{:.2}, {:.2}]", east, north)
+                }
                 Value::Phase(pa, PhaseView::Default(h)) => {
                     let ilf = (1.0 - pa.cos()) / 2.0;
                     let pi = phaseidx(ilf, *pa);
@@ -229,7 +415,10 @@ impl fmt::Display for Value {
                 Value::Phase(pa, PhaseView::PhaseAngle) => {
                     write!(f, "{}", pa.radians())
                 }
+                Value::Num(n) if n.is_nan() => write!(f, "none"),
                 Value::Num(n) => write!(f, "{:0.2}", n),
+                Value::Int(n) => write!(f, "{}", n),
+                Value::Str(s) => write!(f, "\"{}\"", s),
             }
         }
     }